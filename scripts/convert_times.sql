@@ -0,0 +1,22 @@
+-- Converts stop times in place to seconds since midnight of the service day.
+-- Hours may exceed 23 for trips running past midnight. Empty times become NULL.
+-- Needs the time columns declared INT as in schema.sql, else the seconds are stored as text again.
+-- Runs after cleanup.sql, also run by the migrate command on databases imported with "HH:MM:SS" text.
+
+UPDATE stop_time
+SET
+    arrival_time = CASE
+        WHEN typeof(arrival_time) <> 'text' THEN arrival_time
+        WHEN arrival_time = '' THEN NULL
+        ELSE CAST(substr(arrival_time, 1, instr(arrival_time, ':') - 1) AS INT) * 3600
+            + CAST(substr(arrival_time, instr(arrival_time, ':') + 1, 2) AS INT) * 60
+            + CAST(substr(arrival_time, -2) AS INT)
+    END,
+    departure_time = CASE
+        WHEN typeof(departure_time) <> 'text' THEN departure_time
+        WHEN departure_time = '' THEN NULL
+        ELSE CAST(substr(departure_time, 1, instr(departure_time, ':') - 1) AS INT) * 3600
+            + CAST(substr(departure_time, instr(departure_time, ':') + 1, 2) AS INT) * 60
+            + CAST(substr(departure_time, -2) AS INT)
+    END
+WHERE typeof(arrival_time) = 'text' OR typeof(departure_time) = 'text';
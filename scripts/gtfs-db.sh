@@ -27,6 +27,16 @@ echo "Reading cleanup.sql..."
 sqlite3 data.db ".read ../cleanup.sql"
 echo "Done."
 
+# Stop times as seconds
+echo "Reading convert_times.sql..."
+sqlite3 data.db ".read ../convert_times.sql"
+echo "Done."
+
+# Indexes
+echo "Reading index.sql..."
+sqlite3 data.db ".read ../index.sql"
+echo "Done."
+
 # Vacuum
 echo "Starting vacuum..."
 sqlite3 data.db "VACUUM;"
@@ -9,4 +9,5 @@ PRAGMA foreign_keys = ON;
 .import --skip 1 calendar_dates.txt service_exception
 .import --skip 1 trips.txt trip
 .import --skip 1 stop_times.txt stop_time
-
+-- Optional, fails without stopping the import if the feed has no feed_info.txt
+.import --skip 1 feed_info.txt feed_info
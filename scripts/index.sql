@@ -0,0 +1,6 @@
+-- Indexes for the queries of GTFSDatabase, also applied to older databases by the migrate command
+
+CREATE INDEX IF NOT EXISTS stop_time_stop_departure ON stop_time (stop_id, departure_time);
+CREATE INDEX IF NOT EXISTS trip_route ON trip (route_id);
+CREATE INDEX IF NOT EXISTS stop_name ON stop (name);
+CREATE INDEX IF NOT EXISTS trip_short_name ON trip (short_name COLLATE NOCASE);
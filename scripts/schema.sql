@@ -15,12 +15,12 @@ CREATE TABLE service (
     end_date TEXT NOT NULL
 );
 
+-- No foreign key to service: services may be defined in calendar_dates.txt only
 CREATE TABLE service_exception (
     service_id INT,
     service_date TEXT,
     exception_type INT NOT NULL,
-    PRIMARY KEY (service_id, service_date),
-    FOREIGN KEY (service_id) REFERENCES service (service_id)
+    PRIMARY KEY (service_id, service_date)
 );
 
 CREATE TABLE agency (
@@ -68,14 +68,13 @@ CREATE TABLE trip (
     shape_id INT,               -- tmp
     wheelchair_accessible INT,  -- tmp
     bikes_allowed INT,          -- tmp
-    FOREIGN KEY (route_id) REFERENCES route (route_id),
-    FOREIGN KEY (service_id) REFERENCES service (service_id)
+    FOREIGN KEY (route_id) REFERENCES route (route_id)
 );
 
 CREATE TABLE stop_time (
     trip_id INT,
-    arrival_time TEXT,
-    departure_time TEXT,
+    arrival_time INT,       -- seconds after convert_times.sql
+    departure_time INT,     -- seconds after convert_times.sql
     stop_id TEXT,
     stop_sequence INT,
     pickup_type INT,    -- tmp
@@ -86,3 +85,15 @@ CREATE TABLE stop_time (
     FOREIGN KEY (stop_id) REFERENCES stop (stop_id)
 );
 
+-- Optional, columns in the order of feed_info.txt
+CREATE TABLE feed_info (
+    publisher_name TEXT,
+    publisher_url TEXT,
+    lang TEXT,
+    default_lang TEXT,
+    start_date TEXT,
+    end_date TEXT,
+    version TEXT,
+    contact_email TEXT,
+    contact_url TEXT
+);
@@ -0,0 +1,68 @@
+use std::error::Error;
+use std::io::stdout;
+
+use serde::Serialize;
+
+use crate::cli::{Record, resolve_station, write_records};
+use crate::config::BoardArgs;
+use crate::db::GTFSDatabase;
+use crate::db::types::Stop;
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+#[derive(Serialize)]
+pub struct BoardRow {
+    pub trip_id: u32,
+    pub number: String,
+    pub destination: String,
+    pub arrival: String,
+    pub departure: String,
+    pub service_date: String,
+    pub stop_id: String,
+}
+
+impl BoardRow {
+    fn from(stop: &Stop, args: &BoardArgs) -> Self {
+        Self {
+            trip_id: stop.trip_id,
+            number: stop.short_name.to_string(),
+            destination: stop.head_sign.to_string(),
+            arrival: stop.get_adjusted_arr(&args.date_time),
+            departure: stop.get_adjusted_dep(&args.date_time),
+            service_date: stop.service_date(&args.board_type, &args.date_time).format(DATE_FORMAT).to_string(),
+            stop_id: stop.stop_id.to_string(),
+        }
+    }
+}
+
+impl Record for BoardRow {
+    fn header() -> &'static [&'static str] {
+        &["trip_id", "number", "destination", "arrival", "departure", "service_date", "stop_id"]
+    }
+
+    fn fields(&self) -> Vec<String> {
+        vec![
+            self.trip_id.to_string(),
+            self.number.to_string(),
+            self.destination.to_string(),
+            self.arrival.to_string(),
+            self.departure.to_string(),
+            self.service_date.to_string(),
+            self.stop_id.to_string(),
+        ]
+    }
+}
+
+pub fn fetch_rows(db: &GTFSDatabase, args: &BoardArgs) -> Result<Vec<BoardRow>, Box<dyn Error>> {
+    let station = resolve_station(db, &args.station)?;
+
+    Ok(db.fetch_stops(&station.stop_id, args.board_type, args.date_time)?
+        .iter()
+        .take(args.limit.unwrap_or(usize::MAX))
+        .map(|s| BoardRow::from(s, args))
+        .collect())
+}
+
+pub fn run(db: &GTFSDatabase, args: &BoardArgs) -> Result<(), Box<dyn Error>> {
+    write_records(&mut stdout(), &fetch_rows(db, args)?, args.format)
+}
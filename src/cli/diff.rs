@@ -0,0 +1,50 @@
+use std::error::Error;
+use std::io::stdout;
+
+use serde::Serialize;
+
+use crate::cli::{print_progress, Record, write_records};
+use crate::config::DiffArgs;
+use crate::db::GTFSDatabase;
+use crate::db::diff::{Change, diff};
+
+#[derive(Serialize)]
+pub struct ChangeRow {
+    pub change: &'static str,
+    pub entity: &'static str,
+    pub subject: String,
+    pub detail: String,
+}
+
+impl ChangeRow {
+    fn from(change: &Change) -> Self {
+        Self {
+            change: change.kind.name(),
+            entity: change.entity,
+            subject: change.subject.to_string(),
+            detail: change.detail.to_string(),
+        }
+    }
+}
+
+impl Record for ChangeRow {
+    fn header() -> &'static [&'static str] {
+        &["change", "entity", "subject", "detail"]
+    }
+
+    fn fields(&self) -> Vec<String> {
+        vec![
+            self.change.to_string(),
+            self.entity.to_string(),
+            self.subject.to_string(),
+            self.detail.to_string(),
+        ]
+    }
+}
+
+pub fn run(db: &GTFSDatabase, args: &DiffArgs) -> Result<(), Box<dyn Error>> {
+    let old = GTFSDatabase::new(&args.old_db_path, print_progress)?;
+    let rows: Vec<ChangeRow> = diff(&old, db)?.iter().map(ChangeRow::from).collect();
+
+    write_records(&mut stdout(), &rows, args.format)
+}
@@ -0,0 +1,90 @@
+use std::error::Error;
+use std::io::Write;
+
+use serde::Serialize;
+
+use crate::config::OutputFormat;
+use crate::db::GTFSDatabase;
+use crate::db::types::Station;
+
+pub mod board;
+pub mod trip;
+pub mod stations;
+pub mod poster;
+pub mod validate;
+pub mod diff;
+
+const COLUMN_GAP: &str = "  ";
+
+// Rows printable as table and CSV, JSON is serialized from the row itself
+pub trait Record: Serialize {
+    fn header() -> &'static [&'static str];
+    fn fields(&self) -> Vec<String>;
+}
+
+pub fn write_records<T>(
+    out: &mut dyn Write, records: &[T], format: OutputFormat,
+) -> Result<(), Box<dyn Error>>
+    where T: Record
+{
+    match format {
+        OutputFormat::JSON => {
+            serde_json::to_writer_pretty(&mut *out, records)?;
+            writeln!(out)?;
+        }
+        OutputFormat::CSV => {
+            let header: Vec<String> = T::header().iter().map(|h| h.to_string()).collect();
+            writeln!(out, "{}", csv_line(&header))?;
+            for record in records {
+                writeln!(out, "{}", csv_line(&record.fields()))?;
+            }
+        }
+        OutputFormat::TABLE => {
+            let rows: Vec<Vec<String>> = records.iter().map(|r| r.fields()).collect();
+            let widths: Vec<usize> = T::header().iter().enumerate()
+                .map(|(i, h)| rows.iter()
+                    .map(|r| r[i].chars().count())
+                    .fold(h.chars().count(), usize::max))
+                .collect();
+
+            let header: Vec<String> = T::header().iter().map(|h| h.to_string()).collect();
+            for row in std::iter::once(&header).chain(rows.iter()) {
+                let line: Vec<String> = row.iter().zip(widths.iter())
+                    .map(|(f, w)| format!("{:<width$}", f, width = w))
+                    .collect();
+                writeln!(out, "{}", line.join(COLUMN_GAP).trim_end())?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+pub fn csv_line(fields: &[String]) -> String {
+    fields.iter().map(|f| csv_field(f)).collect::<Vec<String>>().join(",")
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+// Station with the given name, otherwise the first one matching the query
+pub fn resolve_station(db: &GTFSDatabase, query: &str) -> Result<Station, Box<dyn Error>> {
+    let mut stations = db.fetch_stations(query)?;
+    let exact = stations.iter().position(|s| s.name.eq_ignore_ascii_case(query));
+
+    match exact {
+        Some(i) => Ok(stations.swap_remove(i)),
+        None if !stations.is_empty() => Ok(stations.swap_remove(0)),
+        None => Err(format!("No station matching '{}'", query).into()),
+    }
+}
+
+// Loading steps go to stderr, keeping stdout for the output
+pub fn print_progress(step: &str) {
+    eprintln!("{}...", step);
+}
@@ -0,0 +1,200 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufWriter, stdout, Write};
+
+use chrono::{Duration, NaiveTime, Timelike};
+
+use crate::cli::resolve_station;
+use crate::config::{PosterArgs, PosterFormat};
+use crate::db::GTFSDatabase;
+use crate::db::types::{BoardType, Stop};
+
+const DATE_FORMAT: &str = "%a %Y-%m-%d";
+const DAILY: &str = "daily";
+const TEXT_WIDTH: usize = 72;
+
+struct Entry {
+    time: NaiveTime,
+    number: String,
+    // Destination for departures, origin for arrivals
+    place: String,
+    footnote: Option<usize>,
+}
+
+// Station timetable grouped by hour, operating days other than daily as footnotes
+struct Poster {
+    station: String,
+    title: String,
+    hours: BTreeMap<u32, Vec<Entry>>,
+    footnotes: Vec<String>,
+}
+
+impl Poster {
+    fn build(db: &GTFSDatabase, args: &PosterArgs) -> Result<Poster, Box<dyn Error>> {
+        let station = resolve_station(db, &args.station)?;
+        let days = if args.week { 7 } else { 1 };
+
+        // A trip running on several days of the week is listed once
+        let mut seen: HashSet<(u32, u32)> = HashSet::new();
+        let mut stops: Vec<(NaiveTime, Stop)> = Vec::new();
+        for day in 0..days {
+            let date = args.date + Duration::days(day);
+            for stop in db.fetch_day_stops(&station.stop_id, args.board_type, date)? {
+                let dt = stop.get_adjusted_dt(&args.board_type, &date.and_hms(0, 0, 0));
+                if let Some(dt) = dt.filter(|_| seen.insert((stop.trip_id, stop.stop_sequence))) {
+                    stops.push((dt.time(), stop));
+                }
+            }
+        }
+        stops.sort_by_key(|(time, _)| *time);
+
+        // Arrivals are labeled with the first station of the trip
+        let origins = match args.board_type {
+            BoardType::ARRIVAL => db.fetch_origins(&station.stop_id)?,
+            BoardType::DEPARTURE => HashMap::new(),
+        };
+        // Keyed by service and the days the stop lies past the service day
+        let mut operating_days: HashMap<(u16, i64), String> = HashMap::new();
+        let mut footnotes: Vec<String> = Vec::new();
+        let mut hours: BTreeMap<u32, Vec<Entry>> = BTreeMap::new();
+
+        for (time, stop) in stops {
+            // A Fri-only train passing at 00:30 stops on Saturdays
+            let offset = stop.board_day_offset(&args.board_type);
+            let days = operating_days.entry((stop.service_id, offset))
+                .or_insert_with(|| db.get_service(stop.service_id)
+                    .map_or(String::new(), |s| s.shifted(offset).operating_days()));
+            let footnote = if days.is_empty() || days == DAILY {
+                None
+            } else {
+                match footnotes.iter().position(|f| f == days) {
+                    Some(i) => Some(i + 1),
+                    None => {
+                        footnotes.push(days.to_string());
+                        Some(footnotes.len())
+                    }
+                }
+            };
+
+            hours.entry(time.hour()).or_default().push(Entry {
+                time,
+                number: stop.short_name.to_string(),
+                place: match args.board_type {
+                    BoardType::ARRIVAL => origins.get(&stop.trip_id).cloned().unwrap_or_default(),
+                    BoardType::DEPARTURE => stop.head_sign.to_string(),
+                },
+                footnote,
+            });
+        }
+
+        let kind = match args.board_type {
+            BoardType::ARRIVAL => "Arrivals",
+            BoardType::DEPARTURE => "Departures",
+        };
+        let title = if args.week {
+            format!(
+                "{} {} - {}", kind,
+                args.date.format(DATE_FORMAT), (args.date + Duration::days(6)).format(DATE_FORMAT),
+            )
+        } else {
+            format!("{} {}", kind, args.date.format(DATE_FORMAT))
+        };
+
+        Ok(Poster { station: station.name, title, hours, footnotes })
+    }
+
+    fn write_text(&self, out: &mut dyn Write) -> Result<(), Box<dyn Error>> {
+        writeln!(out, "{}", self.station)?;
+        writeln!(out, "{}", self.title)?;
+        writeln!(out, "{}", "=".repeat(TEXT_WIDTH))?;
+
+        for (hour, entries) in &self.hours {
+            for (i, entry) in entries.iter().enumerate() {
+                let hour = if i == 0 { format!("{:02}", hour) } else { String::new() };
+                let line = format!(
+                    "{:<2} | {:02}  {:<10} {:<40} {}",
+                    hour, entry.time.minute(), entry.number, entry.place,
+                    entry.footnote.map_or(String::new(), |f| format!("[{}]", f)),
+                );
+                writeln!(out, "{}", line.trim_end())?;
+            }
+            writeln!(out, "{}", "-".repeat(TEXT_WIDTH))?;
+        }
+
+        if !self.footnotes.is_empty() {
+            writeln!(out)?;
+            for (i, note) in self.footnotes.iter().enumerate() {
+                writeln!(out, "[{}] {}", i + 1, note)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn write_html(&self, out: &mut dyn Write) -> Result<(), Box<dyn Error>> {
+        writeln!(out, "<!DOCTYPE html>")?;
+        writeln!(out, "<html>\n<head>\n<meta charset=\"utf-8\">")?;
+        writeln!(out, "<title>{} - {}</title>", escape(&self.station), escape(&self.title))?;
+        writeln!(out, "<style>\n\
+            body {{ font-family: sans-serif; }}\n\
+            table {{ border-collapse: collapse; width: 100%; }}\n\
+            th, td {{ border-bottom: 1px solid #999; padding: 4px 8px; text-align: left; vertical-align: top; }}\n\
+            th {{ font-size: 1.4em; width: 3em; }}\n\
+            .entry {{ display: inline-block; min-width: 16em; margin: 2px 12px 2px 0; }}\n\
+            .minute {{ font-weight: bold; }}\n\
+            @media print {{ tr {{ page-break-inside: avoid; }} }}\n\
+            </style>")?;
+        writeln!(out, "</head>\n<body>")?;
+        writeln!(out, "<h1>{}</h1>\n<h2>{}</h2>", escape(&self.station), escape(&self.title))?;
+        writeln!(out, "<table>")?;
+
+        for (hour, entries) in &self.hours {
+            write!(out, "<tr><th>{:02}</th><td>", hour)?;
+            for entry in entries {
+                write!(
+                    out,
+                    "<span class=\"entry\"><span class=\"minute\">{:02}</span> {} {}{}</span>",
+                    entry.time.minute(), escape(&entry.number), escape(&entry.place),
+                    entry.footnote.map_or(String::new(), |f| format!(" <sup>{}</sup>", f)),
+                )?;
+            }
+            writeln!(out, "</td></tr>")?;
+        }
+        writeln!(out, "</table>")?;
+
+        if !self.footnotes.is_empty() {
+            writeln!(out, "<ol class=\"footnotes\">")?;
+            for note in &self.footnotes {
+                writeln!(out, "<li>{}</li>", escape(note))?;
+            }
+            writeln!(out, "</ol>")?;
+        }
+        writeln!(out, "</body>\n</html>")?;
+
+        Ok(())
+    }
+}
+
+fn escape(value: &str) -> String {
+    value.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+pub fn run(db: &GTFSDatabase, args: &PosterArgs) -> Result<(), Box<dyn Error>> {
+    let poster = Poster::build(db, args)?;
+    let mut out: Box<dyn Write> = match &args.output {
+        Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+        None => Box::new(stdout()),
+    };
+
+    match args.format {
+        PosterFormat::TEXT => poster.write_text(&mut out)?,
+        PosterFormat::HTML => poster.write_html(&mut out)?,
+    }
+    out.flush()?;
+
+    Ok(())
+}
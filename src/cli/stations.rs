@@ -0,0 +1,53 @@
+use std::error::Error;
+use std::io::stdout;
+
+use serde::Serialize;
+
+use crate::cli::{Record, write_records};
+use crate::config::StationsArgs;
+use crate::db::GTFSDatabase;
+use crate::db::types::Station;
+
+#[derive(Serialize)]
+pub struct StationRow {
+    pub stop_id: String,
+    pub name: String,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+}
+
+impl StationRow {
+    fn from(station: &Station) -> Self {
+        Self {
+            stop_id: station.stop_id.to_string(),
+            name: station.name.to_string(),
+            latitude: station.latitude,
+            longitude: station.longitude,
+        }
+    }
+}
+
+impl Record for StationRow {
+    fn header() -> &'static [&'static str] {
+        &["stop_id", "name", "latitude", "longitude"]
+    }
+
+    fn fields(&self) -> Vec<String> {
+        vec![
+            self.stop_id.to_string(),
+            self.name.to_string(),
+            self.latitude.map_or(String::new(), |v| v.to_string()),
+            self.longitude.map_or(String::new(), |v| v.to_string()),
+        ]
+    }
+}
+
+pub fn fetch_rows(db: &GTFSDatabase, args: &StationsArgs) -> Result<Vec<StationRow>, Box<dyn Error>> {
+    Ok(db.fetch_stations(&args.query)?.iter()
+        .map(StationRow::from)
+        .collect())
+}
+
+pub fn run(db: &GTFSDatabase, args: &StationsArgs) -> Result<(), Box<dyn Error>> {
+    write_records(&mut stdout(), &fetch_rows(db, args)?, args.format)
+}
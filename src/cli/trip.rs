@@ -0,0 +1,90 @@
+use std::error::Error;
+use std::io::stdout;
+
+use chrono::{Duration, NaiveDate};
+use serde::Serialize;
+
+use crate::cli::{Record, write_records};
+use crate::config::TripArgs;
+use crate::db::GTFSDatabase;
+use crate::db::types::Stop;
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+#[derive(Serialize)]
+pub struct TripRow {
+    pub trip_id: u32,
+    pub stop_sequence: u32,
+    pub stop_id: String,
+    pub station: String,
+    // Day of the stop, trips may run past midnight
+    pub date: String,
+    pub arrival: String,
+    pub departure: String,
+}
+
+impl TripRow {
+    fn from(stop: &Stop, service_date: NaiveDate) -> Self {
+        let dt = (service_date + Duration::days(stop.day_offset())).and_hms(0, 0, 0);
+
+        Self {
+            trip_id: stop.trip_id,
+            stop_sequence: stop.stop_sequence,
+            stop_id: stop.stop_id.to_string(),
+            station: stop.stop_name.to_string(),
+            date: dt.format(DATE_FORMAT).to_string(),
+            arrival: stop.get_adjusted_arr(&dt),
+            departure: stop.get_adjusted_dep(&dt),
+        }
+    }
+}
+
+impl Record for TripRow {
+    fn header() -> &'static [&'static str] {
+        &["trip_id", "stop_sequence", "stop_id", "station", "date", "arrival", "departure"]
+    }
+
+    fn fields(&self) -> Vec<String> {
+        vec![
+            self.trip_id.to_string(),
+            self.stop_sequence.to_string(),
+            self.stop_id.to_string(),
+            self.station.to_string(),
+            self.date.to_string(),
+            self.arrival.to_string(),
+            self.departure.to_string(),
+        ]
+    }
+}
+
+// The run of the train number operating on the date, or the trip with that trip_id.
+// Numbers are train numbers unless by_id is set, internal ids are rarely what is meant
+pub fn resolve_trip(
+    db: &GTFSDatabase, trip: &str, by_id: bool, date: &NaiveDate,
+) -> Result<Vec<Stop>, Box<dyn Error>> {
+    if by_id {
+        let trip_id = trip.parse::<u32>().map_err(|_| format!("Invalid trip id '{}'", trip))?;
+        let stops = db.fetch_trip(trip_id)?;
+        if stops.is_empty() {
+            return Err(format!("No trip with id {}", trip_id).into());
+        }
+        return Ok(stops);
+    }
+
+    let trip_id = db.find_trips(trip, date)?.into_iter()
+        .find(|r| r.runs_on_date)
+        .map(|r| r.trip_id)
+        .ok_or_else(|| format!("No trip '{}' running on {}", trip, date.format(DATE_FORMAT)))?;
+
+    db.fetch_trip(trip_id)
+}
+
+pub fn fetch_rows(db: &GTFSDatabase, args: &TripArgs) -> Result<Vec<TripRow>, Box<dyn Error>> {
+    Ok(resolve_trip(db, &args.trip, args.by_id, &args.date)?.iter()
+        .map(|s| TripRow::from(s, args.date))
+        .collect())
+}
+
+pub fn run(db: &GTFSDatabase, args: &TripArgs) -> Result<(), Box<dyn Error>> {
+    write_records(&mut stdout(), &fetch_rows(db, args)?, args.format)
+}
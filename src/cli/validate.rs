@@ -0,0 +1,54 @@
+use std::error::Error;
+use std::io::stdout;
+
+use serde::Serialize;
+
+use crate::cli::{Record, write_records};
+use crate::config::ValidateArgs;
+use crate::db::validate::{Finding, Severity, validate};
+
+#[derive(Serialize)]
+pub struct FindingRow {
+    pub severity: &'static str,
+    pub check: &'static str,
+    pub subject: String,
+    pub message: String,
+}
+
+impl FindingRow {
+    fn from(finding: &Finding) -> Self {
+        Self {
+            severity: finding.severity.name(),
+            check: finding.check,
+            subject: finding.subject.to_string(),
+            message: finding.message.to_string(),
+        }
+    }
+}
+
+impl Record for FindingRow {
+    fn header() -> &'static [&'static str] {
+        &["severity", "check", "subject", "message"]
+    }
+
+    fn fields(&self) -> Vec<String> {
+        vec![
+            self.severity.to_string(),
+            self.check.to_string(),
+            self.subject.to_string(),
+            self.message.to_string(),
+        ]
+    }
+}
+
+// Fails if the feed has errors, warnings alone pass
+pub fn run(db_path: &str, args: &ValidateArgs) -> Result<(), Box<dyn Error>> {
+    let findings = validate(db_path)?;
+    let rows: Vec<FindingRow> = findings.iter().map(FindingRow::from).collect();
+    write_records(&mut stdout(), &rows, args.format)?;
+
+    match findings.iter().filter(|f| f.severity == Severity::ERROR).count() {
+        0 => Ok(()),
+        errors => Err(format!("Feed has {} error(s)", errors).into()),
+    }
+}
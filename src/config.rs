@@ -0,0 +1,307 @@
+use std::error::Error;
+use std::time::Duration;
+
+use chrono::{Local, NaiveDate, NaiveDateTime};
+
+use crate::db::spatial::parse_coordinates;
+use crate::db::types::BoardType;
+
+const DEFAULT_DB_PATH: &str = "scripts/data.db";
+const DEFAULT_POLL_SECONDS: u64 = 30;
+const DEFAULT_REPLAY_SPEED: f64 = 1.0;
+const AT_FORMAT: &str = "%Y-%m-%d %H:%M";
+const DATE_FORMAT: &str = "%Y-%m-%d";
+const DEFAULT_HOST: &str = "127.0.0.1";
+const DEFAULT_PORT: u16 = 8080;
+const DEFAULT_EXPORT_DIR: &str = ".";
+
+// What to run, the TUI unless a subcommand is given
+pub enum Command {
+    TUI,
+    BOARD(BoardArgs),
+    TRIP(TripArgs),
+    STATIONS(StationsArgs),
+    SERVE(ServeArgs),
+    POSTER(PosterArgs),
+    VALIDATE(ValidateArgs),
+    DIFF(DiffArgs),
+    // Converts and indexes databases of older imports
+    MIGRATE,
+}
+
+pub struct BoardArgs {
+    pub station: String,
+    pub date_time: NaiveDateTime,
+    pub board_type: BoardType,
+    pub limit: Option<usize>,
+    pub format: OutputFormat,
+}
+
+pub struct TripArgs {
+    // Train number, or the trip_id with by_id
+    pub trip: String,
+    pub by_id: bool,
+    pub date: NaiveDate,
+    pub format: OutputFormat,
+}
+
+pub struct StationsArgs {
+    pub query: String,
+    pub format: OutputFormat,
+}
+
+pub struct ServeArgs {
+    // Localhost unless given, use 0.0.0.0 to serve the LAN
+    pub host: String,
+    pub port: u16,
+}
+
+pub struct PosterArgs {
+    pub station: String,
+    // The day, or the first day of the week
+    pub date: NaiveDate,
+    pub week: bool,
+    pub board_type: BoardType,
+    pub format: PosterFormat,
+    // File to write to, stdout if not given
+    pub output: Option<String>,
+}
+
+pub struct ValidateArgs {
+    pub format: OutputFormat,
+}
+
+pub struct DiffArgs {
+    // Feed database compared against, the new one is opened as --db
+    pub old_db_path: String,
+    pub format: OutputFormat,
+}
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum PosterFormat {
+    TEXT,
+    HTML,
+}
+
+impl PosterFormat {
+    fn from(value: &str) -> Result<PosterFormat, Box<dyn Error>> {
+        match value {
+            "text" => Ok(PosterFormat::TEXT),
+            "html" => Ok(PosterFormat::HTML),
+            _ => Err(format!("Invalid poster format '{}', expected text or html", value).into()),
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum OutputFormat {
+    TABLE,
+    JSON,
+    CSV,
+}
+
+impl OutputFormat {
+    fn from(value: &str) -> Result<OutputFormat, Box<dyn Error>> {
+        match value {
+            "table" => Ok(OutputFormat::TABLE),
+            "json" => Ok(OutputFormat::JSON),
+            "csv" => Ok(OutputFormat::CSV),
+            _ => Err(format!("Invalid format '{}', expected table, json or csv", value).into()),
+        }
+    }
+}
+
+pub struct Config {
+    pub command: Command,
+    pub db_path: String,
+    // Start with a coordinate search around this position
+    pub near: Option<(f64, f64)>,
+    // GTFS-RT feed, file path or http:// URL
+    pub realtime: Option<String>,
+    pub poll_interval: Duration,
+    // Directory to store every fetched snapshot in
+    pub record: Option<String>,
+    // Directory of recorded snapshots, drives the board time
+    pub replay: Option<String>,
+    pub replay_speed: f64,
+    // Directory boards are exported to from the TUI
+    pub export_dir: String,
+    // Older feed database the TUI lists changes against
+    pub compare: Option<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            command: Command::TUI,
+            db_path: String::from(DEFAULT_DB_PATH),
+            near: None,
+            realtime: None,
+            poll_interval: Duration::from_secs(DEFAULT_POLL_SECONDS),
+            record: None,
+            replay: None,
+            replay_speed: DEFAULT_REPLAY_SPEED,
+            export_dir: String::from(DEFAULT_EXPORT_DIR),
+            compare: None,
+        }
+    }
+}
+
+impl Config {
+    pub fn from_args<I>(args: I) -> Result<Config, Box<dyn Error>>
+        where I: IntoIterator<Item = String>
+    {
+        let mut config = Config::default();
+        let mut args = args.into_iter();
+        let mut positional: Vec<String> = Vec::new();
+
+        // Subcommand options
+        let mut date_time = None;
+        let mut date = None;
+        let mut board_type = BoardType::DEPARTURE;
+        let mut limit = None;
+        let mut format: Option<String> = None;
+        let mut week = false;
+        let mut by_id = false;
+        let mut output = None;
+        let mut host = String::from(DEFAULT_HOST);
+        let mut port = DEFAULT_PORT;
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--db" => config.db_path = next_value(&mut args, &arg)?,
+                "--near" => {
+                    let value = next_value(&mut args, &arg)?;
+                    config.near = Some(parse_coordinates(&value).ok_or_else(
+                        || format!("Invalid coordinates '{}', expected <lat>,<lon>", value)
+                    )?);
+                }
+                "--realtime" => config.realtime = Some(next_value(&mut args, &arg)?),
+                "--poll" => {
+                    let value = next_value(&mut args, &arg)?;
+                    config.poll_interval = Duration::from_secs(value.parse().map_err(
+                        |_| format!("Invalid poll interval '{}', expected seconds", value)
+                    )?);
+                }
+                "--record" => config.record = Some(next_value(&mut args, &arg)?),
+                "--export-dir" => config.export_dir = next_value(&mut args, &arg)?,
+                "--compare" => config.compare = Some(next_value(&mut args, &arg)?),
+                "--replay" => config.replay = Some(next_value(&mut args, &arg)?),
+                "--speed" => {
+                    let value = next_value(&mut args, &arg)?;
+                    config.replay_speed = value.parse().ok().filter(|s: &f64| *s > 0.0).ok_or_else(
+                        || format!("Invalid replay speed '{}', expected a positive factor", value)
+                    )?;
+                }
+                "--at" => {
+                    let value = next_value(&mut args, &arg)?;
+                    date_time = Some(parse_date_time(&value)?);
+                }
+                "--date" => {
+                    let value = next_value(&mut args, &arg)?;
+                    date = Some(parse_date(&value)?);
+                }
+                "--arrivals" => board_type = BoardType::ARRIVAL,
+                "--limit" => {
+                    let value = next_value(&mut args, &arg)?;
+                    limit = Some(value.parse().map_err(
+                        |_| format!("Invalid limit '{}', expected a number", value)
+                    )?);
+                }
+                "--host" => host = next_value(&mut args, &arg)?,
+                "--port" => {
+                    let value = next_value(&mut args, &arg)?;
+                    port = value.parse().map_err(|_| format!("Invalid port '{}'", value))?;
+                }
+                "--format" => format = Some(next_value(&mut args, &arg)?),
+                "--week" => week = true,
+                "--id" => by_id = true,
+                "--output" => output = Some(next_value(&mut args, &arg)?),
+                _ if !arg.starts_with("--") => positional.push(arg),
+                _ => return Err(format!("Unknown argument '{}'", arg).into()),
+            }
+        }
+
+        let output_format = || OutputFormat::from(format.as_deref().unwrap_or("table"));
+        let mut positional = positional.into_iter();
+        config.command = match positional.next().as_deref() {
+            None => Command::TUI,
+            Some("board") => Command::BOARD(BoardArgs {
+                station: positional.next().ok_or("Missing station for board")?,
+                date_time: date_time.unwrap_or_else(|| Local::now().naive_local()),
+                board_type,
+                limit,
+                format: output_format()?,
+            }),
+            Some("trip") => Command::TRIP(TripArgs {
+                trip: positional.next().ok_or("Missing train number or trip id for trip")?,
+                by_id,
+                date: date.unwrap_or_else(|| Local::today().naive_local()),
+                format: output_format()?,
+            }),
+            Some("stations") => Command::STATIONS(StationsArgs {
+                query: positional.next().ok_or("Missing query for stations")?,
+                format: output_format()?,
+            }),
+            Some("poster") => Command::POSTER(PosterArgs {
+                station: positional.next().ok_or("Missing station for poster")?,
+                date: date.unwrap_or_else(|| Local::today().naive_local()),
+                week,
+                board_type,
+                format: PosterFormat::from(format.as_deref().unwrap_or("text"))?,
+                output,
+            }),
+            Some("serve") => Command::SERVE(ServeArgs { host, port }),
+            Some("validate") => {
+                // The feed database to check, instead of --db
+                if let Some(path) = positional.next() {
+                    config.db_path = path;
+                }
+                Command::VALIDATE(ValidateArgs { format: output_format()? })
+            }
+            Some("migrate") => {
+                if let Some(path) = positional.next() {
+                    config.db_path = path;
+                }
+                Command::MIGRATE
+            }
+            Some("diff") => {
+                let old_db_path = positional.next().ok_or("Missing old feed for diff")?;
+                config.db_path = positional.next().ok_or("Missing new feed for diff")?;
+                Command::DIFF(DiffArgs { old_db_path, format: output_format()? })
+            }
+            Some(command) => return Err(format!("Unknown command '{}'", command).into()),
+        };
+        if let Some(arg) = positional.next() {
+            return Err(format!("Unexpected argument '{}'", arg).into());
+        }
+
+        if config.replay.is_some() && config.realtime.is_some() {
+            return Err("--replay and --realtime can't be combined".into());
+        }
+        if config.realtime.as_ref().is_some_and(|r| r.starts_with("https://")) {
+            return Err("HTTPS realtime feeds are not supported, use an http:// URL or a local file".into());
+        }
+        if config.record.is_some() && config.realtime.is_none() {
+            return Err("--record requires --realtime".into());
+        }
+
+        Ok(config)
+    }
+}
+
+pub fn parse_date_time(value: &str) -> Result<NaiveDateTime, Box<dyn Error>> {
+    NaiveDateTime::parse_from_str(value, AT_FORMAT)
+        .map_err(|_| format!("Invalid time '{}', expected YYYY-MM-DD HH:MM", value).into())
+}
+
+pub fn parse_date(value: &str) -> Result<NaiveDate, Box<dyn Error>> {
+    NaiveDate::parse_from_str(value, DATE_FORMAT)
+        .map_err(|_| format!("Invalid date '{}', expected YYYY-MM-DD", value).into())
+}
+
+fn next_value<I>(args: &mut I, name: &str) -> Result<String, Box<dyn Error>>
+    where I: Iterator<Item = String>
+{
+    args.next().ok_or_else(|| format!("Missing value for {}", name).into())
+}
@@ -0,0 +1,81 @@
+use std::collections::HashMap;
+
+use chrono::{Duration, NaiveDate};
+
+use crate::db::types::Service;
+
+// Active days of every service compiled into bitsets, built once at startup so availability
+// checks do not scan exceptions. Each service only covers its own validity range and
+// exception dates, a single far-off end date does not widen the others.
+pub struct ServiceDays {
+    days: HashMap<u16, ServiceBits>,
+}
+
+struct ServiceBits {
+    first_date: NaiveDate,
+    day_count: usize,
+    bits: Vec<u64>,
+}
+
+impl ServiceBits {
+    fn new(service: &Service) -> ServiceBits {
+        let dates = service.exceptions.iter().map(|e| e.exception_date)
+            .chain(vec![service.start_date, service.end_date]);
+        let first_date = dates.clone().min().unwrap_or(service.start_date);
+        let last_date = dates.max().unwrap_or(first_date);
+
+        let day_count = (last_date - first_date).num_days() as usize + 1;
+        let mut bits = vec![0_u64; day_count.div_ceil(64)];
+        for offset in 0..day_count {
+            if service.is_available(&(first_date + Duration::days(offset as i64))) {
+                bits[offset / 64] |= 1 << (offset % 64);
+            }
+        }
+
+        ServiceBits { first_date, day_count, bits }
+    }
+
+    fn is_set(&self, offset: usize) -> bool {
+        self.bits[offset / 64] & (1 << (offset % 64)) != 0
+    }
+
+    fn offset_of(&self, date: &NaiveDate) -> Option<usize> {
+        let offset = (*date - self.first_date).num_days();
+        if offset < 0 || offset >= self.day_count as i64 {
+            None
+        } else {
+            Some(offset as usize)
+        }
+    }
+}
+
+impl ServiceDays {
+    pub fn new(services: &HashMap<u16, Service>) -> ServiceDays {
+        let days = services.iter()
+            .map(|(id, service)| (*id, ServiceBits::new(service)))
+            .collect();
+
+        ServiceDays { days }
+    }
+
+    pub fn is_active(&self, service_id: u16, date: &NaiveDate) -> bool {
+        self.days.get(&service_id)
+            .and_then(|s| s.offset_of(date).map(|offset| s.is_set(offset)))
+            .unwrap_or(false)
+    }
+
+    pub fn is_ever_active(&self, service_id: u16) -> bool {
+        self.days.get(&service_id).is_some_and(|s| s.bits.iter().any(|b| *b != 0))
+    }
+
+    // Dates the service runs on, in order
+    pub fn active_dates(&self, service_id: u16) -> Vec<NaiveDate> {
+        match self.days.get(&service_id) {
+            Some(s) => (0..s.day_count)
+                .filter(|offset| s.is_set(*offset))
+                .map(|offset| s.first_date + Duration::days(offset as i64))
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+}
@@ -0,0 +1,394 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::rc::Rc;
+
+use chrono::NaiveDate;
+use rusqlite::Result;
+
+use crate::db::{GTFSDatabase, route_name, trip_number};
+
+// Trips further apart are reported as removed and added rather than as one changed trip
+const MAX_PAIRING_SECONDS: i64 = 60 * 60;
+
+// First and last date both feeds have service on, running dates outside are not compared
+type DateWindow = Option<(NaiveDate, NaiveDate)>;
+
+const STATION_NAMES_QUERY: &str = "SELECT DISTINCT name FROM stop WHERE name IS NOT NULL;";
+
+// Stop times of every trip in sequence, read in one pass
+const TRIP_PATTERNS_QUERY: &str = "SELECT \
+    st.trip_id, t.short_name, r.route_id, r.short_name, r.long_name, s.name, st.arrival_time, st.departure_time, \
+    CAST(t.service_id AS INT) \
+    FROM stop_time st \
+    INNER JOIN trip t ON t.trip_id = st.trip_id \
+    INNER JOIN route r ON r.route_id = t.route_id \
+    INNER JOIN stop s ON s.stop_id = st.stop_id \
+    ORDER BY st.trip_id, st.stop_sequence;";
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ChangeKind {
+    ADDED,
+    REMOVED,
+    CHANGED,
+}
+
+impl ChangeKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ChangeKind::ADDED => "added",
+            ChangeKind::REMOVED => "removed",
+            ChangeKind::CHANGED => "changed",
+        }
+    }
+}
+
+pub struct Change {
+    pub kind: ChangeKind,
+    // "station", "route" or "trip"
+    pub entity: &'static str,
+    pub subject: String,
+    pub detail: String,
+    // Trip in the new feed, for added and changed trips
+    pub trip_id: Option<u32>,
+}
+
+//region TripPattern
+struct TripPattern {
+    trip_id: u32,
+    stops: Vec<PatternStop>,
+    // Dates the trip runs on, shared by the trips of a service
+    dates: Rc<Vec<NaiveDate>>,
+}
+
+#[derive(PartialEq)]
+struct PatternStop {
+    // Shared by every stop at the station
+    name: Rc<str>,
+    // Seconds since midnight of the service day
+    arrival: Option<i64>,
+    departure: Option<i64>,
+}
+
+impl TripPattern {
+    fn first_departure(&self) -> i64 {
+        self.stops.first().and_then(|s| s.departure).unwrap_or_default()
+    }
+
+    fn is_same(&self, other: &TripPattern, window: DateWindow) -> bool {
+        self.stops == other.stops && self.dates_in(window).eq(other.dates_in(window))
+    }
+
+    fn dates_in(&self, window: DateWindow) -> impl Iterator<Item = &NaiveDate> {
+        self.dates.iter().filter(move |d| window.is_some_and(|(first, last)| first <= **d && **d <= last))
+    }
+
+    fn describe(&self, number: &str) -> String {
+        match (self.stops.first(), self.stops.last()) {
+            (Some(first), Some(last)) => format!(
+                "{} {} {} → {}", number, format_time(first.departure), first.name, last.name,
+            ),
+            _ => number.to_string(),
+        }
+    }
+}
+//endregion
+
+impl GTFSDatabase {
+    // First and last date any service runs on
+    fn service_range(&self) -> Option<(NaiveDate, NaiveDate)> {
+        let bounds: Vec<NaiveDate> = self.services.keys()
+            .flat_map(|id| {
+                let dates = self.service_days.active_dates(*id);
+                dates.first().copied().into_iter().chain(dates.last().copied())
+            })
+            .collect();
+
+        Some((*bounds.iter().min()?, *bounds.iter().max()?))
+    }
+
+    fn fetch_station_names(&self) -> Result<BTreeSet<String>> {
+        let mut stmt = self.db.prepare(STATION_NAMES_QUERY)?;
+        let iter = stmt.query_map([], |row| row.get(0))?;
+
+        iter.collect()
+    }
+
+    // Trips keyed by route name and train number, ids may change between feed versions
+    fn fetch_trip_patterns(&self) -> Result<BTreeMap<(String, String), Vec<TripPattern>>> {
+        let mut patterns: BTreeMap<(String, String), Vec<TripPattern>> = BTreeMap::new();
+        let mut current: Option<((String, String), TripPattern)> = None;
+        let mut service_dates: HashMap<u16, Rc<Vec<NaiveDate>>> = HashMap::new();
+        let mut names: HashSet<Rc<str>> = HashSet::new();
+
+        let mut stmt = self.db.prepare(TRIP_PATTERNS_QUERY)?;
+        let mut rows = stmt.query([])?;
+        while let Some(row) = rows.next()? {
+            let trip_id: u32 = row.get(0)?;
+            if current.as_ref().is_none_or(|(_, p)| p.trip_id != trip_id) {
+                if let Some((key, pattern)) = current.take() {
+                    patterns.entry(key).or_default().push(pattern);
+                }
+
+                let route_id: String = row.get(2)?;
+                let route_short_name: Option<String> = row.get(3)?;
+                let number = Some(trip_number(row.get(1)?, route_short_name.clone()))
+                    .filter(|n| !n.is_empty())
+                    .unwrap_or_else(|| trip_id.to_string());
+                let route = route_name(route_short_name, row.get(4)?, &route_id);
+                let service_id: u16 = row.get(8)?;
+                let dates = service_dates.entry(service_id)
+                    .or_insert_with(|| Rc::new(self.service_days.active_dates(service_id)))
+                    .clone();
+                current = Some(((route, number), TripPattern { trip_id, stops: Vec::new(), dates }));
+            }
+
+            if let Some((_, pattern)) = &mut current {
+                let name = row.get::<usize, Option<String>>(5)?.unwrap_or_default();
+                let name = match names.get(name.as_str()) {
+                    Some(interned) => interned.clone(),
+                    None => {
+                        let interned: Rc<str> = Rc::from(name);
+                        names.insert(interned.clone());
+                        interned
+                    }
+                };
+                pattern.stops.push(PatternStop {
+                    name,
+                    arrival: row.get(6)?,
+                    departure: row.get(7)?,
+                });
+            }
+        }
+        if let Some((key, pattern)) = current {
+            patterns.entry(key).or_default().push(pattern);
+        }
+
+        Ok(patterns)
+    }
+}
+
+// Stations, routes and trips added, removed or changed from the old to the new feed
+pub fn diff(old: &GTFSDatabase, new: &GTFSDatabase) -> Result<Vec<Change>> {
+    let mut changes = Vec::new();
+
+    let old_stations = old.fetch_station_names()?;
+    let new_stations = new.fetch_station_names()?;
+    for name in old_stations.difference(&new_stations) {
+        changes.push(change(ChangeKind::REMOVED, "station", name.to_string(), String::new(), None));
+    }
+    for name in new_stations.difference(&old_stations) {
+        changes.push(change(ChangeKind::ADDED, "station", name.to_string(), String::new(), None));
+    }
+
+    // Routes by agency and name
+    let routes = |db: &GTFSDatabase| -> Result<BTreeMap<String, i32>> {
+        Ok(db.fetch_routes("")?.iter()
+            .map(|r| (format!("{} ({})", r.display_name(), r.agency), r.route_type))
+            .collect())
+    };
+    let old_routes = routes(old)?;
+    let new_routes = routes(new)?;
+    for (name, route_type) in &old_routes {
+        match new_routes.get(name) {
+            None => changes.push(change(ChangeKind::REMOVED, "route", name.to_string(), String::new(), None)),
+            Some(t) if t != route_type => changes.push(change(
+                ChangeKind::CHANGED, "route", name.to_string(),
+                format!("route_type {} → {}", route_type, t), None,
+            )),
+            _ => {}
+        }
+    }
+    for name in new_routes.keys().filter(|n| !old_routes.contains_key(*n)) {
+        changes.push(change(ChangeKind::ADDED, "route", name.to_string(), String::new(), None));
+    }
+
+    // Feed versions usually cover shifted windows, running dates are compared where both have service
+    let window = match (old.service_range(), new.service_range()) {
+        (Some((old_first, old_last)), Some((new_first, new_last))) => {
+            Some((old_first.max(new_first), old_last.min(new_last))).filter(|(first, last)| first <= last)
+        }
+        _ => None,
+    };
+
+    let mut old_trips = old.fetch_trip_patterns()?;
+    let new_trips = new.fetch_trip_patterns()?;
+    for ((route, number), mut new_group) in new_trips {
+        let old_group = old_trips.remove(&(route, number.to_string())).unwrap_or_default();
+        diff_trips(&mut changes, &number, window, old_group, &mut new_group);
+    }
+    for ((_, number), old_group) in old_trips {
+        diff_trips(&mut changes, &number, window, old_group, &mut Vec::new());
+    }
+
+    Ok(changes)
+}
+
+// Trips sharing route and number: identical ones drop out, the rest are paired by departure
+// within MAX_PAIRING_SECONDS
+fn diff_trips(
+    changes: &mut Vec<Change>, number: &str, window: DateWindow,
+    mut old_group: Vec<TripPattern>, new_group: &mut Vec<TripPattern>,
+) {
+    old_group.retain(|o| match new_group.iter().position(|n| n.is_same(o, window)) {
+        Some(i) => {
+            new_group.remove(i);
+            false
+        }
+        None => true,
+    });
+    old_group.sort_by_key(|p| p.first_departure());
+
+    for old_trip in old_group {
+        let closest = new_group.iter().enumerate()
+            .map(|(i, n)| (i, (n.first_departure() - old_trip.first_departure()).abs()))
+            .filter(|(_, distance)| *distance <= MAX_PAIRING_SECONDS)
+            .min_by_key(|(_, distance)| *distance)
+            .map(|(i, _)| i);
+
+        match closest {
+            Some(i) => {
+                let new_trip = new_group.remove(i);
+                changes.push(change(
+                    ChangeKind::CHANGED, "trip", new_trip.describe(number),
+                    time_changes(&old_trip, &new_trip, window), Some(new_trip.trip_id),
+                ));
+            }
+            None => changes.push(change(
+                ChangeKind::REMOVED, "trip", old_trip.describe(number), String::new(), None,
+            )),
+        }
+    }
+
+    for new_trip in new_group.drain(..) {
+        changes.push(change(
+            ChangeKind::ADDED, "trip", new_trip.describe(number), String::new(), Some(new_trip.trip_id),
+        ));
+    }
+}
+
+// e.g. "Hamburg Hbf arr 09:45 → 09:50; +Spandau; -Potsdam Hbf; -2 dates from 2026-12-24"
+fn time_changes(old: &TripPattern, new: &TripPattern, window: DateWindow) -> String {
+    let mut details = Vec::new();
+
+    for old_stop in &old.stops {
+        match new.stops.iter().find(|s| s.name == old_stop.name) {
+            Some(new_stop) => {
+                let mut times = Vec::new();
+                if old_stop.arrival != new_stop.arrival {
+                    times.push(format!("arr {} → {}", format_time(old_stop.arrival), format_time(new_stop.arrival)));
+                }
+                if old_stop.departure != new_stop.departure {
+                    times.push(format!("dep {} → {}", format_time(old_stop.departure), format_time(new_stop.departure)));
+                }
+                if !times.is_empty() {
+                    details.push(format!("{} {}", old_stop.name, times.join(", ")));
+                }
+            }
+            None => details.push(format!("-{}", old_stop.name)),
+        }
+    }
+    for new_stop in new.stops.iter().filter(|n| !old.stops.iter().any(|o| o.name == n.name)) {
+        details.push(format!("+{}", new_stop.name));
+    }
+
+    // Dates are in order
+    let removed: Vec<&NaiveDate> = old.dates_in(window).filter(|d| new.dates.binary_search(d).is_err()).collect();
+    let added: Vec<&NaiveDate> = new.dates_in(window).filter(|d| old.dates.binary_search(d).is_err()).collect();
+    for (sign, dates) in [("-", removed), ("+", added)].iter() {
+        if let Some(first) = dates.first() {
+            details.push(format!("{}{} date(s) from {}", sign, dates.len(), first.format("%Y-%m-%d")));
+        }
+    }
+
+    details.join("; ")
+}
+
+// Hours past 23 are kept, like in the feed
+fn format_time(seconds: Option<i64>) -> String {
+    match seconds {
+        Some(s) => format!("{:02}:{:02}", s / 3600, s / 60 % 60),
+        None => String::from("--:--"),
+    }
+}
+
+fn change(
+    kind: ChangeKind, entity: &'static str, subject: String, detail: String, trip_id: Option<u32>,
+) -> Change {
+    Change { kind, entity, subject, detail, trip_id }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use chrono::{Duration, NaiveDate};
+
+    use crate::db::diff::{ChangeKind, DateWindow, diff_trips, PatternStop, TripPattern};
+
+    fn date(value: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    // Daily from first to last
+    fn daily(first: &str, last: &str) -> Vec<NaiveDate> {
+        let days = (date(last) - date(first)).num_days();
+        (0..=days).map(|d| date(first) + Duration::days(d)).collect()
+    }
+
+    // From A at departure to B one hour later
+    fn pattern(trip_id: u32, departure: i64, dates: Vec<NaiveDate>) -> TripPattern {
+        TripPattern {
+            trip_id,
+            stops: vec![
+                PatternStop { name: Rc::from("A"), arrival: None, departure: Some(departure) },
+                PatternStop { name: Rc::from("B"), arrival: Some(departure + 3600), departure: None },
+            ],
+            dates: Rc::new(dates),
+        }
+    }
+
+    // (kind, detail, trip_id) of each change
+    fn run(
+        window: DateWindow, old: Vec<TripPattern>, mut new: Vec<TripPattern>,
+    ) -> Vec<(ChangeKind, String, Option<u32>)> {
+        let mut changes = Vec::new();
+        diff_trips(&mut changes, "ICE 598", window, old, &mut new);
+        changes.into_iter().map(|c| (c.kind, c.detail, c.trip_id)).collect()
+    }
+
+    #[test]
+    fn identical_trips_drop_out() {
+        let old = vec![pattern(1, 8 * 3600, Vec::new()), pattern(2, 10 * 3600, Vec::new())];
+        let new = vec![pattern(12, 10 * 3600, Vec::new()), pattern(11, 8 * 3600, Vec::new())];
+
+        assert!(run(None, old, new).is_empty());
+    }
+
+    #[test]
+    fn trips_pair_by_departure_within_an_hour() {
+        let old = vec![pattern(1, 8 * 3600, Vec::new()), pattern(2, 12 * 3600, Vec::new())];
+        let new = vec![pattern(11, 8 * 3600 + 600, Vec::new()), pattern(12, 14 * 3600, Vec::new())];
+
+        let changes = run(None, old, new);
+        assert_eq!(changes.len(), 3);
+        assert_eq!(changes[0], (ChangeKind::CHANGED, String::from("A dep 08:00 → 08:10; B arr 09:00 → 09:10"), Some(11)));
+        assert_eq!(changes[1], (ChangeKind::REMOVED, String::new(), None));
+        assert_eq!(changes[2], (ChangeKind::ADDED, String::new(), Some(12)));
+    }
+
+    #[test]
+    fn dates_are_compared_within_the_window() {
+        let window = Some((date("2026-10-19"), date("2026-10-25")));
+
+        // Shifted feed windows, the same days where both have service
+        let old = vec![pattern(1, 8 * 3600, daily("2026-10-12", "2026-10-25"))];
+        let new = vec![pattern(11, 8 * 3600, daily("2026-10-19", "2026-11-01"))];
+        assert!(run(window, old, new).is_empty());
+
+        let mut dates = daily("2026-10-19", "2026-11-01");
+        dates.retain(|d| d != &date("2026-10-21"));
+        let old = vec![pattern(1, 8 * 3600, daily("2026-10-12", "2026-10-25"))];
+        let new = vec![pattern(11, 8 * 3600, dates)];
+        let changes = run(window, old, new);
+        assert_eq!(changes, [(ChangeKind::CHANGED, String::from("-1 date(s) from 2026-10-21"), Some(11))]);
+    }
+}
@@ -0,0 +1,89 @@
+use chrono::Duration;
+
+use crate::db::types::Stop;
+
+// Timetable of a route like a printed Kursbuch table, stops as rows and trips as columns
+pub struct RouteGrid {
+    pub route_name: String,
+    pub trips: Vec<GridTrip>,
+    pub stops: Vec<GridStop>,
+    // Departure, or arrival at the last stop, indexed [stop][trip]
+    pub times: Vec<Vec<Option<String>>>,
+}
+
+pub struct GridTrip {
+    pub trip_id: u32,
+    pub number: String,
+}
+
+pub struct GridStop {
+    pub stop_id: String,
+    pub name: String,
+}
+
+impl RouteGrid {
+    // Trips with their stops in sequence, ordered by their first departure
+    pub fn new(route_name: String, mut trips: Vec<(GridTrip, Vec<Stop>)>) -> RouteGrid {
+        trips.retain(|(_, stops)| !stops.is_empty());
+        trips.sort_by_key(|(_, stops)| stops[0].departure_time);
+
+        let stops = merge_stop_order(&trips);
+        let times = stops.iter()
+            .map(|grid_stop| trips.iter()
+                .map(|(_, trip_stops)| {
+                    let last = trip_stops.len() - 1;
+                    trip_stops.iter().enumerate()
+                        .find(|(_, s)| s.stop_id == grid_stop.stop_id)
+                        .map(|(i, s)| {
+                            let time = if i == last {
+                                s.arrival_time.or(s.departure_time)
+                            } else {
+                                s.departure_time.or(s.arrival_time)
+                            };
+                            // Passed without a scheduled time
+                            time.map(format_time).unwrap_or_else(|| String::from("|"))
+                        })
+                })
+                .collect())
+            .collect();
+
+        RouteGrid {
+            route_name,
+            trips: trips.into_iter().map(|(trip, _)| trip).collect(),
+            stops,
+            times,
+        }
+    }
+}
+
+// Stop order covering every trip, starting from the longest one.
+// Stops missing so far are inserted after the previous stop of the trip serving them.
+fn merge_stop_order(trips: &[(GridTrip, Vec<Stop>)]) -> Vec<GridStop> {
+    let mut by_length: Vec<&Vec<Stop>> = trips.iter().map(|(_, stops)| stops).collect();
+    by_length.sort_by_key(|stops| std::cmp::Reverse(stops.len()));
+
+    let mut order: Vec<GridStop> = Vec::new();
+    for stops in by_length {
+        let mut insert_at = 0;
+        for stop in stops {
+            match order.iter().position(|s| s.stop_id == stop.stop_id) {
+                Some(i) => insert_at = i + 1,
+                None => {
+                    order.insert(insert_at, GridStop {
+                        stop_id: stop.stop_id.to_string(),
+                        name: stop.stop_name.to_string(),
+                    });
+                    insert_at += 1;
+                }
+            }
+        }
+    }
+
+    order
+}
+
+// Times of trips past midnight wrap to the next day
+fn format_time(time: Duration) -> String {
+    let time = time - Duration::days(time.num_days());
+    format!("{:02}:{:02}", time.num_hours(), time.num_minutes() % 60)
+}
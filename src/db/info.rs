@@ -0,0 +1,146 @@
+use chrono::{Datelike, Duration, NaiveDate};
+
+// Days with fewer trips than this share of the median of their weekday count as low service
+const LOW_SERVICE_SHARE: f64 = 0.5;
+
+// Overview of a loaded feed, to check the right dataset is in use
+pub struct FeedSummary {
+    pub feed_info: Option<FeedInfo>,
+    // First and last date any service runs
+    pub validity: Option<(NaiveDate, NaiveDate)>,
+    pub table_counts: Vec<(&'static str, u64)>,
+    pub median_trips: u32,
+    pub low_service: Vec<LowService>,
+}
+
+pub struct FeedInfo {
+    pub publisher_name: String,
+    pub publisher_url: String,
+    pub version: String,
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+}
+
+// Consecutive days with unusually few trips
+pub struct LowService {
+    pub first_date: NaiveDate,
+    pub last_date: NaiveDate,
+    // Fewest trips on a day of the range
+    pub min_trips: u32,
+}
+
+impl FeedSummary {
+    // Trips running on each day, starting with the first date
+    pub fn new(
+        feed_info: Option<FeedInfo>, table_counts: Vec<(&'static str, u64)>,
+        first_date: NaiveDate, daily_trips: &[u32],
+    ) -> FeedSummary {
+        // Days before the first or after the last service are not part of the range
+        let first_active = daily_trips.iter().position(|t| *t > 0);
+        let last_active = daily_trips.iter().rposition(|t| *t > 0);
+        let (start, end) = match (first_active, last_active) {
+            (Some(s), Some(e)) => (s, e),
+            _ => return FeedSummary {
+                feed_info,
+                validity: None,
+                table_counts,
+                median_trips: 0,
+                low_service: Vec::new(),
+            },
+        };
+        let active = &daily_trips[start..=end];
+        let date_of = |offset: usize| first_date + Duration::days((start + offset) as i64);
+
+        // Weekends run less than weekdays, so each day is compared with its own weekday
+        let mut by_weekday: [Vec<u32>; 7] = Default::default();
+        for (offset, trips) in active.iter().enumerate() {
+            by_weekday[date_of(offset).weekday().num_days_from_monday() as usize].push(*trips);
+        }
+        let weekday_medians: Vec<u32> = by_weekday.iter().map(|trips| median(trips)).collect();
+
+        let mut low_service: Vec<LowService> = Vec::new();
+        for (offset, trips) in active.iter().enumerate() {
+            let weekday_median = weekday_medians[date_of(offset).weekday().num_days_from_monday() as usize];
+            if *trips as f64 >= weekday_median as f64 * LOW_SERVICE_SHARE {
+                continue;
+            }
+            match low_service.last_mut() {
+                Some(range) if range.last_date == date_of(offset) - Duration::days(1) => {
+                    range.last_date = date_of(offset);
+                    range.min_trips = range.min_trips.min(*trips);
+                }
+                _ => low_service.push(LowService {
+                    first_date: date_of(offset),
+                    last_date: date_of(offset),
+                    min_trips: *trips,
+                }),
+            }
+        }
+
+        FeedSummary {
+            feed_info,
+            validity: Some((date_of(0), date_of(active.len() - 1))),
+            table_counts,
+            median_trips: median(active),
+            low_service,
+        }
+    }
+}
+
+// Upper median, 0 without any days
+fn median(trips: &[u32]) -> u32 {
+    let mut sorted = trips.to_vec();
+    sorted.sort_unstable();
+    sorted.get(sorted.len() / 2).copied().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Weeks starting on a Monday with the given trips Monday to Sunday
+    fn weeks(days: &[[u32; 7]]) -> Vec<u32> {
+        days.iter().flat_map(|week| week.iter().copied()).collect()
+    }
+
+    fn low_ranges(summary: &FeedSummary) -> Vec<(NaiveDate, NaiveDate, u32)> {
+        summary.low_service.iter().map(|r| (r.first_date, r.last_date, r.min_trips)).collect()
+    }
+
+    #[test]
+    fn weekends_are_compared_with_weekends() {
+        let monday = NaiveDate::from_ymd(2026, 10, 5);
+        let daily_trips = weeks(&[
+            [100, 100, 100, 100, 100, 40, 30],
+            [100, 100, 20, 100, 100, 40, 30],
+            [100, 100, 100, 40, 45, 40, 10],
+            [100, 100, 100, 100, 100, 40, 30],
+        ]);
+
+        let summary = FeedSummary::new(None, Vec::new(), monday, &daily_trips);
+
+        assert_eq!(summary.median_trips, 100);
+        assert_eq!(summary.validity, Some((monday, NaiveDate::from_ymd(2026, 11, 1))));
+        assert_eq!(low_ranges(&summary), vec![
+            (NaiveDate::from_ymd(2026, 10, 14), NaiveDate::from_ymd(2026, 10, 14), 20),
+            (NaiveDate::from_ymd(2026, 10, 22), NaiveDate::from_ymd(2026, 10, 23), 40),
+            (NaiveDate::from_ymd(2026, 10, 25), NaiveDate::from_ymd(2026, 10, 25), 10),
+        ]);
+    }
+
+    #[test]
+    fn days_without_service_outside_the_range_are_ignored() {
+        let first_date = NaiveDate::from_ymd(2026, 10, 3);
+        let daily_trips = [0, 0, 50, 50, 0, 50, 0];
+
+        let summary = FeedSummary::new(None, Vec::new(), first_date, &daily_trips);
+
+        assert_eq!(summary.validity, Some((NaiveDate::from_ymd(2026, 10, 5), NaiveDate::from_ymd(2026, 10, 8))));
+        // Each weekday occurs once, no day is below the median of its weekday
+        assert!(summary.low_service.is_empty());
+
+        let empty = FeedSummary::new(None, Vec::new(), first_date, &[0, 0]);
+        assert_eq!(empty.validity, None);
+        assert!(empty.low_service.is_empty());
+    }
+}
@@ -1,176 +1,748 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::error::Error;
 
-use chrono::{Duration, NaiveDateTime};
-use regex::Regex;
-use rusqlite::{Connection, Result, Row};
+use chrono::{Duration, Local, NaiveDate, NaiveDateTime, Timelike};
+use rusqlite::{Connection, OpenFlags, params, Result, Row};
 
-use crate::db::types::{BoardType, Service, ServiceException, Station, Stop, Weekday};
-use crate::db::util::{str_to_date, str_to_dur};
+use crate::db::types::{AgencyStats, BoardType, Route, Service, ServiceException, Station, Stop, TripRun, Weekday};
+use crate::db::calendar::ServiceDays;
+use crate::db::spatial::StationIndex;
+use crate::db::grid::{GridTrip, RouteGrid};
+use crate::db::info::{FeedInfo, FeedSummary};
+use crate::db::util::str_to_date;
 
 mod util;
 pub mod types;
+pub mod spatial;
+pub mod operating_days;
+pub mod calendar;
+pub mod grid;
+pub mod info;
+pub mod validate;
+pub mod diff;
 
 //region Queries
-const SERVICE_QUERY: &str = "SELECT s.*, se.service_date, se.exception_type \
-    FROM service s \
-    LEFT JOIN service_exception se \
-    ON se.service_id = s.service_id;";
+const SERVICE_QUERY: &str = "SELECT * FROM service;";
 
-const STOP_QUERY: &str = "SELECT \
-    st.arrival_time, st.departure_time, t.trip_id, s.service_id, t.short_name, t.headsign \
-    FROM stop_time st \
-    INNER JOIN trip t ON t.trip_id = st.trip_id \
-    INNER JOIN service s ON s.service_id = t.service_id \
-    INNER JOIN route r ON r.route_id = t.route_id \
-    INNER JOIN agency a ON a.agency_id = r.agency_id
-    WHERE st.stop_id LIKE ?1;";
+const SERVICE_EXCEPTION_QUERY: &str = "SELECT service_id, service_date, exception_type \
+    FROM service_exception;";
+
+const SECONDS_PER_DAY: u32 = 86400;
+
+// Times are seconds since midnight of the service day, trips may run into the next day
+fn get_stop_query(board_type: &BoardType) -> String {
+    let time_column = match board_type {
+        BoardType::ARRIVAL => "st.arrival_time",
+        BoardType::DEPARTURE => "st.departure_time",
+    };
+
+    format!(
+        "SELECT \
+        st.arrival_time, st.departure_time, t.trip_id, CAST(t.service_id AS INT), t.short_name, t.headsign, \
+        st.stop_id, st.stop_sequence, t.route_id, CAST(r.agency_id AS TEXT), r.route_type, s.name \
+        FROM stop_time st \
+        INNER JOIN stop s ON s.stop_id = st.stop_id \
+        INNER JOIN trip t ON t.trip_id = st.trip_id \
+        INNER JOIN route r ON r.route_id = t.route_id \
+        WHERE st.stop_id >= ?1 AND st.stop_id < ?2 \
+        AND (({col} > ?3 AND {col} < {day}) OR ({col} > ?3 + {day} AND {col} < 2 * {day}));",
+        col = time_column,
+        day = SECONDS_PER_DAY,
+    )
+}
 
 const TRIP_QUERY: &str = "SELECT
-    st.arrival_time, st.departure_time, st.trip_id, 0, '', s.name \
+    st.arrival_time, st.departure_time, st.trip_id, CAST(t.service_id AS INT), t.short_name, t.headsign, \
+    st.stop_id, st.stop_sequence, t.route_id, CAST(r.agency_id AS TEXT), r.route_type, s.name \
     FROM stop_time st \
     INNER JOIN stop s on s.stop_id = st.stop_id \
+    INNER JOIN trip t on t.trip_id = st.trip_id \
+    INNER JOIN route r on r.route_id = t.route_id \
     WHERE st.trip_id = ?1 \
     ORDER BY st.stop_sequence;";
 
-fn get_station_query(input: &str) -> String {
-    let filter = match input.is_empty() {
-        true => String::from("'%Hbf' OR name LIKE '%Hauptbahnhof'"),
-        false => format!("'%{}%'", input)
-    };
+// Stops of the trips named ?1, or of route ?2 with trip name ?3, e.g. "598" or "ICE" and "598"
+const TRIP_NUMBER_QUERY: &str = "SELECT \
+    t.trip_id, CAST(t.service_id AS INT), t.short_name, r.short_name, s.name, st.departure_time \
+    FROM trip t \
+    INNER JOIN route r ON r.route_id = t.route_id \
+    INNER JOIN stop_time st ON st.trip_id = t.trip_id \
+    INNER JOIN stop s ON s.stop_id = st.stop_id \
+    WHERE t.short_name = ?1 COLLATE NOCASE \
+    OR (t.short_name = ?3 COLLATE NOCASE AND r.short_name = ?2 COLLATE NOCASE) \
+    ORDER BY t.trip_id, st.stop_sequence;";
 
-    format!(
-        "SELECT MIN(stop_id), name FROM stop WHERE name LIKE {} GROUP BY name;",
-        filter
-    )
+// The optional direction_id is imported as '' when empty
+const TRIP_ROUTE_QUERY: &str = "SELECT route_id, NULLIF(direction_id, '') FROM trip WHERE trip_id = ?1;";
+
+// First station of every trip calling at the stop, MIN() picks the name of the same row
+const ORIGINS_QUERY: &str = "SELECT st.trip_id, s.name, MIN(st.stop_sequence) \
+    FROM stop_time st \
+    INNER JOIN stop s ON s.stop_id = st.stop_id \
+    WHERE st.trip_id IN (SELECT trip_id FROM stop_time WHERE stop_id >= ?1 AND stop_id < ?2) \
+    GROUP BY st.trip_id;";
+
+const ROUTE_QUERY: &str = "SELECT short_name, long_name FROM route WHERE route_id = ?1;";
+
+const ROUTE_TRIPS_QUERY: &str = "SELECT trip_id, CAST(service_id AS INT), short_name \
+    FROM trip WHERE route_id = ?1 AND NULLIF(direction_id, '') IS ?2;";
+
+const ROUTES_QUERY: &str = "SELECT r.route_id, r.short_name, r.long_name, r.route_type, a.name \
+    FROM route r \
+    LEFT JOIN agency a ON a.agency_id = r.agency_id \
+        OR (IFNULL(r.agency_id, '') = '' AND (SELECT COUNT(*) FROM agency) = 1) \
+    WHERE ?1 = '' OR r.short_name LIKE ?2 OR r.long_name LIKE ?2 \
+    ORDER BY r.short_name, r.long_name;";
+
+const ROUTE_DIRECTIONS_QUERY: &str = "SELECT DISTINCT NULLIF(direction_id, '') FROM trip WHERE route_id = ?1 \
+    ORDER BY 1;";
+
+// Routes without an agency_id belong to the agency of a single-agency feed
+const AGENCY_QUERY: &str = "SELECT CAST(a.agency_id AS TEXT), a.name, \
+    COUNT(DISTINCT r.route_id), COUNT(DISTINCT t.trip_id) \
+    FROM agency a \
+    LEFT JOIN route r ON r.agency_id = a.agency_id \
+        OR (IFNULL(r.agency_id, '') = '' AND (SELECT COUNT(*) FROM agency) = 1) \
+    LEFT JOIN trip t ON t.route_id = r.route_id \
+    GROUP BY a.agency_id \
+    ORDER BY a.name;";
+
+// The agency of a route, empty if it has none in a feed of several agencies
+const AGENCY_STOPS_QUERY: &str = "SELECT \
+    COALESCE(NULLIF(CAST(r.agency_id AS TEXT), ''), \
+        (SELECT CAST(agency_id AS TEXT) FROM agency WHERE (SELECT COUNT(*) FROM agency) = 1), ''), \
+    COUNT(DISTINCT st.stop_id) \
+    FROM stop_time st \
+    INNER JOIN trip t ON t.trip_id = st.trip_id \
+    INNER JOIN route r ON r.route_id = t.route_id \
+    GROUP BY 1;";
+
+// Trips and departures per service, the last stop of a trip is no departure
+const AGENCY_SERVICES_QUERY: &str = "SELECT \
+    COALESCE(NULLIF(CAST(r.agency_id AS TEXT), ''), \
+        (SELECT CAST(agency_id AS TEXT) FROM agency WHERE (SELECT COUNT(*) FROM agency) = 1), ''), \
+    CAST(t.service_id AS INT), \
+    COUNT(DISTINCT t.trip_id), COUNT(st.trip_id) \
+    FROM trip t \
+    INNER JOIN route r ON r.route_id = t.route_id \
+    LEFT JOIN stop_time st ON st.trip_id = t.trip_id AND st.departure_time IS NOT NULL \
+    AND st.stop_sequence < (SELECT MAX(l.stop_sequence) FROM stop_time l WHERE l.trip_id = t.trip_id) \
+    GROUP BY 1, t.service_id;";
+
+// Tables counted in the feed summary, feed_info is optional
+const SUMMARY_TABLES: [&str; 7] = ["agency", "route", "stop", "trip", "stop_time", "service", "service_exception"];
+
+const FEED_INFO_EXISTS_QUERY: &str = "SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'feed_info';";
+
+const FEED_INFO_QUERY: &str = "SELECT publisher_name, publisher_url, version, start_date, end_date \
+    FROM feed_info LIMIT 1;";
+
+const SERVICE_TRIPS_QUERY: &str = "SELECT CAST(service_id AS INT), COUNT(*) FROM trip GROUP BY service_id;";
+
+const STATION_QUERY: &str = "SELECT MIN(stop_id), name, NULLIF(latitude, ''), NULLIF(longitude, '') \
+    FROM stop WHERE name LIKE ?1 OR name LIKE ?2 GROUP BY name;";
+
+fn get_station_filters(input: &str) -> [String; 2] {
+    match input.is_empty() {
+        true => [String::from("%Hbf"), String::from("%Hauptbahnhof")],
+        false => [format!("%{}%", input), format!("%{}%", input)],
+    }
 }
+
+const LOCATION_QUERY: &str = "SELECT NULLIF(latitude, ''), NULLIF(longitude, '') FROM stop WHERE stop_id = ?1;";
+
+// One entry per station, platforms are grouped with their parent. Empty CSV fields are imported as ''
+const ALL_STATIONS_QUERY: &str = "SELECT MIN(stop_id), name, latitude, longitude \
+    FROM stop \
+    WHERE typeof(latitude) IN ('real', 'integer') AND typeof(longitude) IN ('real', 'integer') \
+    GROUP BY COALESCE(NULLIF(parent_station, ''), stop_id);";
+
+const INDEX_SQL: &str = include_str!("../../scripts/index.sql");
+
+const TEXT_TIME_QUERY: &str = "SELECT 1 FROM stop_time \
+    WHERE typeof(arrival_time) = 'text' OR typeof(departure_time) = 'text' LIMIT 1;";
+
+const TEXT_TIME_COLUMN_QUERY: &str = "SELECT 1 FROM pragma_table_info('stop_time') \
+    WHERE name IN ('arrival_time', 'departure_time') AND type <> 'INT';";
+
+const STOP_TIME_COLUMNS_QUERY: &str = "SELECT name, type FROM pragma_table_info('stop_time') ORDER BY cid;";
+
+const CONVERT_TIMES_SQL: &str = include_str!("../../scripts/convert_times.sql");
 //endregion
 
 pub struct GTFSDatabase {
     db: Connection,
     services: HashMap<u16, Service>,
-    time_regex: Regex,
+    service_days: ServiceDays,
+    station_index: StationIndex,
 }
 
 impl GTFSDatabase {
-    pub fn new(db_path: &str) -> Result<GTFSDatabase, Box<dyn Error>> {
-        let db = Connection::open(db_path)?;
+    // Opened read-only, databases of older imports are upgraded by migrate.
+    // Each loading step is passed to progress before it starts
+    pub fn new<F>(db_path: &str, progress: F) -> Result<GTFSDatabase, Box<dyn Error>>
+    where
+        F: Fn(&str),
+    {
+        let db = Connection::open_with_flags(db_path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+        // Only the declared type, checking every row is left to validate and migrate
+        if db.prepare(TEXT_TIME_COLUMN_QUERY)?.exists([])? {
+            return Err(format!("Stop times of '{}' are stored as text, run the migrate command once", db_path).into());
+        }
+        progress("Mapping services");
         let services = fetch_services(&db)?;
+        let service_days = ServiceDays::new(&services);
+        progress("Indexing stations");
+        let station_index = StationIndex::new(fetch_all_stations(&db)?);
         Ok(GTFSDatabase {
             db,
             services,
-            time_regex: Regex::new(r"(?P<hours>\d{1,2}):(?P<minutes>\d{2}):(?P<seconds>\d{2})")?,
+            service_days,
+            station_index,
         })
     }
 
     pub fn fetch_stations(&self, input: &str) -> Result<Vec<Station>> {
-        let mut stmt = self.db.prepare(&get_station_query(input))?;
-        let iter = stmt.query_map([], |row| {
-            Ok(Station {
-                stop_id: row.get(0)?,
-                name: row.get(1)?,
-            })
-        })?;
+        let mut stmt = self.db.prepare_cached(STATION_QUERY)?;
+        let iter = stmt.query_map(get_station_filters(input), map_station)?;
 
-        Ok(iter.map(|s| s.unwrap()).collect())
+        iter.collect()
+    }
+
+    // None for unknown stops and stops without coordinates
+    pub fn fetch_station_location(&self, stop_id: &str) -> Result<Option<(f64, f64)>> {
+        let mut stmt = self.db.prepare_cached(LOCATION_QUERY)?;
+        let mut rows = stmt.query([stop_id])?;
+
+        match rows.next()? {
+            Some(row) => Ok(row.get::<usize, Option<f64>>(0)?.zip(row.get::<usize, Option<f64>>(1)?)),
+            None => Ok(None),
+        }
+    }
+
+    // Stations within radius_km around the given position, nearest first
+    pub fn fetch_nearby_stations(
+        &self, latitude: f64, longitude: f64, radius_km: f64,
+    ) -> Vec<Station> {
+        self.station_index.nearby(latitude, longitude, radius_km)
     }
 
     pub fn fetch_stops(
         &self, stop_id: &str, board_type: BoardType, date_time: NaiveDateTime,
     ) -> Result<Vec<Stop>, Box<dyn Error>> {
+        let seconds = i64::from(date_time.time().num_seconds_from_midnight());
+        let mut stops: Vec<Stop> = self.query_stops(stop_id, &board_type, seconds)?.into_iter()
+            // F0: Remove unavailable service
+            .filter(|s| self.service_days.is_active(s.service_id, &s.service_date(&board_type, &date_time)))
+            // F1: Apply time filter
+            .filter(|s| s.is_after_adjusted_time(&board_type, &date_time))
+            .collect();
+
+        stops.sort_by_key(|s| s.get_adjusted_dt(&board_type, &date_time));
+
+        Ok(stops)
+    }
+
+    // Every stop of the calendar day, including trips of the previous day running past midnight
+    pub fn fetch_day_stops(
+        &self, stop_id: &str, board_type: BoardType, date: NaiveDate,
+    ) -> Result<Vec<Stop>, Box<dyn Error>> {
+        let date_time = date.and_hms(0, 0, 0);
+        let mut stops: Vec<Stop> = self.query_stops(stop_id, &board_type, -1)?.into_iter()
+            .filter(|s| self.service_days.is_active(s.service_id, &s.service_date(&board_type, &date_time)))
+            .collect();
+
+        stops.sort_by_key(|s| s.get_adjusted_dt(&board_type, &date_time));
+
+        Ok(stops)
+    }
+
+    // Stops later than the given seconds after midnight, on the day or the previous service day
+    fn query_stops(
+        &self, stop_id: &str, board_type: &BoardType, after_seconds: i64,
+    ) -> Result<Vec<Stop>> {
         if stop_id.is_empty() {
-            Ok(Vec::new())
-        } else {
-            let mut stmt = self.db.prepare(STOP_QUERY)?;
-            // let iter = stmt.query_map([stop_id], |row| self.map_stop(&row))?;
-            let iter = stmt.query_map(
-                [format!("{}%", stop_id)],
-                |row| self.map_stop(&row)
-            )?;
-            let mut stops: Vec<Stop> = iter.map(|s| s.unwrap())
-                // F0: Remove unavailable service
-                .filter(|s| self.services.get(&s.service_id).unwrap().is_available(
-                    &(date_time.date() - Duration::days(s.arrival_time.num_days()))
-                ))
-                // F1: Apply time filter
-                .filter(|s| s.is_after_adjusted_time(&board_type, &date_time))
-                .collect();
-
-            stops.sort_by(|a, b| a.get_adjusted_dt(&board_type, &date_time).cmp(
-                &b.get_adjusted_dt(&board_type, &date_time)));
-
-            Ok(stops)
+            return Ok(Vec::new());
         }
+
+        let mut stmt = self.db.prepare_cached(&get_stop_query(board_type))?;
+        // Prefix range, also matches platforms like "<stop_id>:1"
+        let iter = stmt.query_map(
+            params![
+                stop_id,
+                format!("{}{}", stop_id, char::MAX),
+                after_seconds,
+            ],
+            map_stop,
+        )?;
+
+        iter.collect()
+    }
+
+    // Origin station names keyed by trip_id, for the trips calling at the stop or its platforms
+    pub fn fetch_origins(&self, stop_id: &str) -> Result<HashMap<u32, String>> {
+        let mut stmt = self.db.prepare_cached(ORIGINS_QUERY)?;
+        let iter = stmt.query_map(
+            params![stop_id, format!("{}{}", stop_id, char::MAX)],
+            |row| Ok((row.get(0)?, row.get::<usize, Option<String>>(1)?.unwrap_or_default())),
+        )?;
+
+        iter.collect()
     }
 
     pub fn fetch_trip(&self, trip_id: u32) -> Result<Vec<Stop>, Box<dyn Error>> {
-        let mut stmt = self.db.prepare(TRIP_QUERY)?;
-        let iter = stmt.query_map([trip_id], |row| self.map_stop(&row))?;
+        let mut stmt = self.db.prepare_cached(TRIP_QUERY)?;
+        let iter = stmt.query_map([trip_id], map_stop)?;
 
-        Ok(iter.map(|s| s.unwrap()).collect())
+        Ok(iter.collect::<Result<_>>()?)
     }
 
-    fn map_stop(&self, row: &Row) -> Result<Stop> {
-        Ok(Stop {
-            arrival_time: str_to_dur(&self.time_regex, row.get(0)?).unwrap(),
-            departure_time: str_to_dur(&self.time_regex, row.get(1)?).unwrap(),
-            trip_id: row.get(2)?,
-            short_name: row.get(4)?,
-            service_id: row.get(3)?,
-            head_sign: row.get(5)?,
-        })
+    // Runs of the given train number, those operating on the date first.
+    // "ICE 598" also matches trip "598" of route "ICE"
+    pub fn find_trips(&self, number: &str, date: &NaiveDate) -> Result<Vec<TripRun>> {
+        let number = number.trim();
+        let (route, trip) = match number.rsplit_once(' ') {
+            Some((route, trip)) => (Some(route.trim_end()), Some(trip)),
+            None => (None, None),
+        };
+
+        let mut stmt = self.db.prepare_cached(TRIP_NUMBER_QUERY)?;
+        let mut rows = stmt.query(params![number, route, trip])?;
+        let mut runs: Vec<TripRun> = Vec::new();
+        // Runs of a number mostly share a few services
+        let mut operating_days: HashMap<u16, String> = HashMap::new();
+        while let Some(row) = rows.next()? {
+            let trip_id: u32 = row.get(0)?;
+            let stop_name = row.get::<usize, Option<String>>(4)?.unwrap_or_default();
+
+            // Rows of a trip are in stop order, the first is the origin and the last the destination
+            match runs.last_mut() {
+                Some(run) if run.trip_id == trip_id => run.destination = stop_name,
+                _ => {
+                    let service_id: u16 = row.get(1)?;
+                    let days = operating_days.entry(service_id).or_insert_with(
+                        || self.get_service(service_id).map_or(String::new(), |s| s.operating_days())
+                    );
+                    runs.push(TripRun {
+                        trip_id,
+                        number: trip_number(row.get(2)?, row.get(3)?),
+                        origin: stop_name.to_string(),
+                        destination: stop_name,
+                        departure_time: Duration::seconds(row.get::<usize, Option<i64>>(5)?.unwrap_or_default()),
+                        runs_on_date: self.is_service_active(service_id, date),
+                        operating_days: days.to_string(),
+                    });
+                }
+            }
+        }
+        runs.sort_by_key(|r| (!r.runs_on_date, r.departure_time));
+
+        Ok(runs)
+    }
+
+    // Routes with the input in their name, all routes for an empty input
+    pub fn fetch_routes(&self, input: &str) -> Result<Vec<Route>> {
+        let mut stmt = self.db.prepare_cached(ROUTES_QUERY)?;
+        let iter = stmt.query_map(params![input, format!("%{}%", input)], |row| Ok(Route {
+            route_id: row.get(0)?,
+            short_name: row.get::<usize, Option<String>>(1)?.unwrap_or_default(),
+            long_name: row.get::<usize, Option<String>>(2)?.unwrap_or_default(),
+            route_type: row.get(3)?,
+            agency: row.get::<usize, Option<String>>(4)?.unwrap_or_default(),
+        }))?;
+
+        iter.collect()
+    }
+
+    pub fn fetch_route_directions(&self, route_id: &str) -> Result<Vec<Option<u32>>> {
+        let mut stmt = self.db.prepare_cached(ROUTE_DIRECTIONS_QUERY)?;
+        let iter = stmt.query_map([route_id], |row| row.get(0))?;
+
+        iter.collect()
+    }
+
+    // Route and direction of a trip
+    pub fn fetch_trip_route(&self, trip_id: u32) -> Result<Option<(String, Option<u32>)>> {
+        let mut stmt = self.db.prepare_cached(TRIP_ROUTE_QUERY)?;
+        let mut rows = stmt.query([trip_id])?;
+
+        match rows.next()? {
+            Some(row) => Ok(Some((row.get(0)?, row.get(1)?))),
+            None => Ok(None),
+        }
+    }
+
+    // Trips of a route and direction operating on the date
+    pub fn fetch_route_grid(
+        &self, route_id: &str, direction_id: Option<u32>, date: &NaiveDate,
+    ) -> Result<RouteGrid, Box<dyn Error>> {
+        let mut stmt = self.db.prepare_cached(ROUTE_QUERY)?;
+        let (short_name, long_name): (Option<String>, Option<String>) = stmt
+            .query_row([route_id], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        let route_name = route_name(short_name, long_name, route_id);
+
+        let mut stmt = self.db.prepare_cached(ROUTE_TRIPS_QUERY)?;
+        let runs: Vec<(u32, u16, Option<String>)> = stmt
+            .query_map(params![route_id, direction_id], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+            .collect::<Result<_>>()?;
+
+        let mut trips = Vec::new();
+        for (trip_id, service_id, number) in runs {
+            if self.service_days.is_active(service_id, date) {
+                let number = Some(trip_number(number, None))
+                    .filter(|n| !n.is_empty())
+                    .unwrap_or_else(|| trip_id.to_string());
+                trips.push((GridTrip { trip_id, number }, self.fetch_trip(trip_id)?));
+            }
+        }
+
+        Ok(RouteGrid::new(route_name, trips))
+    }
+
+    pub fn fetch_agency_stats(&self, date: &NaiveDate) -> Result<Vec<AgencyStats>> {
+        let mut stmt = self.db.prepare_cached(AGENCY_STOPS_QUERY)?;
+        let stops: HashMap<String, u32> = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<_>>()?;
+
+        // Services are resolved here, the calendar is not queryable in SQL
+        let mut on_date: HashMap<String, (u32, u32)> = HashMap::new();
+        let mut stmt = self.db.prepare_cached(AGENCY_SERVICES_QUERY)?;
+        let rows = stmt.query_map([], |row| Ok((
+            row.get::<usize, String>(0)?, row.get::<usize, u16>(1)?, row.get::<usize, u32>(2)?, row.get::<usize, u32>(3)?,
+        )))?;
+        for row in rows {
+            let (agency_id, service_id, trips, departures) = row?;
+            if self.service_days.is_active(service_id, date) {
+                let counts = on_date.entry(agency_id).or_default();
+                counts.0 += trips;
+                counts.1 += departures;
+            }
+        }
+
+        let mut stmt = self.db.prepare_cached(AGENCY_QUERY)?;
+        let iter = stmt.query_map([], |row| {
+            let agency_id: String = row.get(0)?;
+            let (trips_on_date, departures) = on_date.get(&agency_id).copied().unwrap_or_default();
+            Ok(AgencyStats {
+                stops: stops.get(&agency_id).copied().unwrap_or_default(),
+                name: row.get(1)?,
+                routes: row.get(2)?,
+                trips: row.get(3)?,
+                trips_on_date,
+                departures,
+                agency_id,
+            })
+        })?;
+
+        iter.collect()
+    }
+
+    pub fn fetch_feed_summary(&self) -> Result<FeedSummary, Box<dyn Error>> {
+        let mut table_counts = Vec::new();
+        for table in SUMMARY_TABLES.iter() {
+            let count: u64 = self.db.query_row(&format!("SELECT COUNT(*) FROM {};", table), [], |row| row.get(0))?;
+            table_counts.push((*table, count));
+        }
+
+        let feed_info = if self.db.prepare(FEED_INFO_EXISTS_QUERY)?.exists([])? {
+            table_counts.push(("feed_info", self.db.query_row("SELECT COUNT(*) FROM feed_info;", [], |row| row.get(0))?));
+            let mut stmt = self.db.prepare(FEED_INFO_QUERY)?;
+            let mut rows = stmt.query([])?;
+            match rows.next()? {
+                Some(row) => {
+                    // Dates are optional in feed_info.txt
+                    let date = |i: usize| -> Result<Option<NaiveDate>> {
+                        Ok(row.get::<usize, Option<String>>(i)?
+                            .filter(|d| !d.is_empty())
+                            .and_then(|d| str_to_date(d).ok()))
+                    };
+                    Some(FeedInfo {
+                        publisher_name: row.get::<usize, Option<String>>(0)?.unwrap_or_default(),
+                        publisher_url: row.get::<usize, Option<String>>(1)?.unwrap_or_default(),
+                        version: row.get::<usize, Option<String>>(2)?.unwrap_or_default(),
+                        start_date: date(3)?,
+                        end_date: date(4)?,
+                    })
+                }
+                None => None,
+            }
+        } else {
+            None
+        };
+
+        let mut stmt = self.db.prepare(SERVICE_TRIPS_QUERY)?;
+        let service_trips: Vec<(u16, u32)> = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<_>>()?;
+
+        // Only the days services actually run on are visited
+        let mut trips_by_date: BTreeMap<NaiveDate, u32> = BTreeMap::new();
+        for (service_id, trips) in service_trips {
+            for date in self.service_days.active_dates(service_id) {
+                *trips_by_date.entry(date).or_default() += trips;
+            }
+        }
+
+        let first_date = trips_by_date.keys().next().copied().unwrap_or_else(|| Local::today().naive_local());
+        let last_date = trips_by_date.keys().next_back().copied().unwrap_or(first_date);
+        let daily_trips: Vec<u32> = (0..=(last_date - first_date).num_days())
+            .map(|offset| trips_by_date.get(&(first_date + Duration::days(offset))).copied().unwrap_or(0))
+            .collect();
+
+        Ok(FeedSummary::new(feed_info, table_counts, first_date, &daily_trips))
+    }
+
+    pub fn is_service_active(&self, service_id: u16, date: &NaiveDate) -> bool {
+        self.service_days.is_active(service_id, date)
+    }
+
+    // First date from the given one the service runs on, else the last one before it
+    pub fn nearest_service_date(&self, service_id: u16, date: &NaiveDate) -> Option<NaiveDate> {
+        let dates = self.service_days.active_dates(service_id);
+        dates.iter().find(|d| *d >= date).or_else(|| dates.last()).copied()
+    }
+
+    pub fn get_service(&self, service_id: u16) -> Option<&Service> {
+        self.services.get(&service_id)
+    }
+}
+
+fn map_stop(row: &Row) -> Result<Stop> {
+    Ok(Stop {
+        arrival_time: row.get::<usize, Option<i64>>(0)?.map(Duration::seconds),
+        departure_time: row.get::<usize, Option<i64>>(1)?.map(Duration::seconds),
+        trip_id: row.get(2)?,
+        stop_id: row.get(6)?,
+        stop_sequence: row.get(7)?,
+        stop_name: row.get::<usize, Option<String>>(11)?.unwrap_or_default(),
+        short_name: row.get(4)?,
+        service_id: row.get(3)?,
+        head_sign: row.get::<usize, Option<String>>(5)?.unwrap_or_default(),
+        route_id: row.get(8)?,
+        agency_id: row.get(9)?,
+        route_type: row.get(10)?,
+    })
+}
+
+// "ICE 598" from route "ICE" and trip "598", unless the trip name already contains the route
+fn trip_number(trip_short_name: Option<String>, route_short_name: Option<String>) -> String {
+    match (trip_short_name.filter(|n| !n.is_empty()), route_short_name.filter(|n| !n.is_empty())) {
+        (Some(t), Some(r)) if !t.starts_with(&r) => format!("{} {}", r, t),
+        (Some(t), _) => t,
+        (None, r) => r.unwrap_or_default(),
+    }
+}
+
+// "ICE Berlin - Hamburg" from the short and long name, the id if both are empty
+fn route_name(short_name: Option<String>, long_name: Option<String>, route_id: &str) -> String {
+    match (short_name.filter(|n| !n.is_empty()), long_name.filter(|n| !n.is_empty())) {
+        (Some(s), Some(l)) => format!("{} {}", s, l),
+        (s, l) => s.or(l).unwrap_or_else(|| route_id.to_string()),
+    }
+}
+
+fn map_station(row: &Row) -> Result<Station> {
+    Ok(Station {
+        stop_id: row.get(0)?,
+        name: row.get(1)?,
+        latitude: row.get(2)?,
+        longitude: row.get(3)?,
+        distance_km: None,
+    })
+}
+
+// Compatibility path for databases imported before times were stored as seconds or indexed.
+// Each step is passed to progress before it starts
+pub fn migrate<F>(db_path: &str, progress: F) -> Result<(), Box<dyn Error>>
+where
+    F: Fn(&str),
+{
+    let db = Connection::open_with_flags(db_path, OpenFlags::SQLITE_OPEN_READ_WRITE)?;
+    if db.prepare(TEXT_TIME_COLUMN_QUERY)?.exists([])? {
+        progress("Declaring stop time columns as INT");
+        retype_time_columns(&db)?;
+    }
+    if db.prepare(TEXT_TIME_QUERY)?.exists([])? {
+        progress("Converting stop times to seconds");
+        db.execute_batch(CONVERT_TIMES_SQL)?;
     }
+    progress("Creating indexes");
+    db.execute_batch(INDEX_SQL)?;
+
+    Ok(())
+}
+
+// TEXT affinity would store the converted seconds as text again.
+// Rebuilds stop_time with INT times, keeping every other column as it is
+fn retype_time_columns(db: &Connection) -> Result<()> {
+    let mut stmt = db.prepare(STOP_TIME_COLUMNS_QUERY)?;
+    let columns = stmt.query_map([], |row| {
+        let name: String = row.get(0)?;
+        let column_type: String = row.get(1)?;
+        Ok(match name.as_str() {
+            "arrival_time" | "departure_time" => format!("{} INT", name),
+            _ => format!("{} {}", name, column_type),
+        })
+    })?.collect::<Result<Vec<String>>>()?;
+
+    db.execute_batch(&format!(
+        "BEGIN TRANSACTION; \
+        CREATE TABLE stop_time_int ({}, \
+            PRIMARY KEY (trip_id, stop_sequence), \
+            FOREIGN KEY (trip_id) REFERENCES trip (trip_id), \
+            FOREIGN KEY (stop_id) REFERENCES stop (stop_id)); \
+        INSERT INTO stop_time_int SELECT * FROM stop_time; \
+        DROP TABLE stop_time; \
+        ALTER TABLE stop_time_int RENAME TO stop_time; \
+        COMMIT;",
+        columns.join(", "),
+    ))
+}
+
+//Called once at startup to build the spatial index
+fn fetch_all_stations(db: &Connection) -> Result<Vec<Station>> {
+    let mut stmt = db.prepare(ALL_STATIONS_QUERY)?;
+    let iter = stmt.query_map([], map_station)?;
+
+    iter.collect()
 }
 
 //Called once at startup
 //TODO: Consider lazy evaluation
-pub fn fetch_services(db: &Connection) -> Result<HashMap<u16, Service>, Box<dyn Error>> {
-    let mut stmt = db.prepare(SERVICE_QUERY)?;
+fn fetch_services(db: &Connection) -> Result<HashMap<u16, Service>, Box<dyn Error>> {
+    let mut map: HashMap<u16, Service> = HashMap::new();
 
+    let mut stmt = db.prepare(SERVICE_QUERY)?;
     let mut rows = stmt.query([])?;
-    println!("Mapping services...");
+    while let Some(row) = rows.next()? {
+        let operating_weekdays = Weekday::from_rows(
+            row.get(1)?,
+            row.get(2)?,
+            row.get(3)?,
+            row.get(4)?,
+            row.get(5)?,
+            row.get(6)?,
+            row.get(7)?,
+        );
 
-    let mut map: HashMap<u16, Service> = HashMap::new();
+        map.insert(row.get(0)?, Service::new(
+            str_to_date(row.get(8)?)?,
+            str_to_date(row.get(9)?)?,
+            operating_weekdays,
+        ));
+    }
 
+    let mut stmt = db.prepare(SERVICE_EXCEPTION_QUERY)?;
+    let mut rows = stmt.query([])?;
     while let Some(row) = rows.next()? {
-        let service_id = row.get(0)?;
-
-        // Components
-        let exception = match row.get::<usize, String>(10) {
-            Ok(x) => Some(
-                ServiceException {
-                    exception_date: str_to_date(x)?,
-                    exception_type: row.get(11)?,
-                }
-            ),
-            Err(_) => None,
+        let exception = ServiceException {
+            exception_date: str_to_date(row.get(1)?)?,
+            exception_type: row.get(2)?,
         };
 
-        if !map.contains_key(&service_id) {
-            let operating_weekdays = Weekday::from_rows(
-                row.get(1)?,
-                row.get(2)?,
-                row.get(3)?,
-                row.get(4)?,
-                row.get(5)?,
-                row.get(6)?,
-                row.get(7)?,
-            );
-
-            let service = Service::new(
-                str_to_date(row.get(8)?)?,
-                str_to_date(row.get(9)?)?,
-                operating_weekdays,
-            );
-
-            map.insert(service_id, service);
-        }
+        // Services defined only in calendar_dates.txt have no weekly pattern
+        let service = map.entry(row.get(0)?).or_insert_with(|| Service::new(
+            exception.exception_date,
+            exception.exception_date,
+            Weekday::empty(),
+        ));
+        service.add_exception(exception);
+    }
 
-        // Add exception to service exceptions if exists
-        if let Some(x) = exception {
-            map.get_mut(&service_id).unwrap().exceptions.push(x);
+    Ok(map)
+}
+
+#[cfg(test)]
+mod tests {
+    use rusqlite::{Connection, params_from_iter};
+    use rusqlite::types::Null;
+
+    use crate::db::{
+        fetch_all_stations, get_stop_query, map_station, INDEX_SQL, ROUTE_DIRECTIONS_QUERY, ROUTE_TRIPS_QUERY,
+        STATION_QUERY, TRIP_NUMBER_QUERY, TRIP_QUERY,
+    };
+    use crate::db::types::BoardType;
+
+    const SCHEMA_SQL: &str = include_str!("../../scripts/schema.sql");
+
+    // Queries run on every board, trip or trip search update, these must not scan stop_time
+    #[test]
+    fn hot_queries_use_indexes() {
+        let db = Connection::open_in_memory().unwrap();
+        db.execute_batch(SCHEMA_SQL).unwrap();
+        db.execute_batch(INDEX_SQL).unwrap();
+
+        let hot_queries = [
+            ("departure board", get_stop_query(&BoardType::DEPARTURE)),
+            ("arrival board", get_stop_query(&BoardType::ARRIVAL)),
+            ("trip", TRIP_QUERY.to_string()),
+            ("trip number", TRIP_NUMBER_QUERY.to_string()),
+        ];
+
+        for (name, query) in hot_queries.iter() {
+            let mut stmt = db.prepare(&format!("EXPLAIN QUERY PLAN {}", query)).unwrap();
+            let params = vec![Null; stmt.parameter_count()];
+            let details: Vec<String> = stmt
+                .query_map(params_from_iter(params), |row| row.get(3))
+                .unwrap()
+                .collect::<rusqlite::Result<_>>()
+                .unwrap();
+
+            for detail in details {
+                assert!(!detail.starts_with("SCAN"), "{} query: {}", name, detail);
+            }
         }
     }
 
-    Ok(map)
+    #[test]
+    fn all_stations_keep_same_named_stops_apart() {
+        let db = Connection::open_in_memory().unwrap();
+        db.execute_batch(SCHEMA_SQL).unwrap();
+        db.execute_batch("INSERT INTO stop (stop_id, name, latitude, longitude, parent_station) VALUES \
+            ('A', 'Kirche', 52.5, 13.4, ''), \
+            ('B', 'Kirche', 48.1, 11.6, ''), \
+            ('C', 'Bahnhof', 50.1, 8.7, ''), \
+            ('C:1', 'Bahnhof', 50.1001, 8.7001, 'C'), \
+            ('D', 'Schule', '', '', '');").unwrap();
+
+        let stations = fetch_all_stations(&db).unwrap();
+        let ids: Vec<&str> = stations.iter().map(|s| s.stop_id.as_str()).collect();
+
+        assert_eq!(ids, ["A", "B", "C"]);
+    }
+
+    #[test]
+    fn station_search_finds_stops_without_coordinates() {
+        let db = Connection::open_in_memory().unwrap();
+        db.execute_batch(SCHEMA_SQL).unwrap();
+        db.execute_batch("INSERT INTO stop (stop_id, name, latitude, longitude) VALUES \
+            ('D', 'Schule', '', '');").unwrap();
+
+        let stations: Vec<_> = db.prepare(STATION_QUERY).unwrap()
+            .query_map(["%Schule%", "%Schule%"], map_station).unwrap()
+            .collect::<rusqlite::Result<_>>().unwrap();
+
+        assert_eq!(stations.len(), 1);
+        assert_eq!(stations[0].location(), None);
+    }
+
+    #[test]
+    fn empty_direction_is_no_direction() {
+        let db = Connection::open_in_memory().unwrap();
+        db.execute_batch(SCHEMA_SQL).unwrap();
+        db.execute_batch("INSERT INTO route (route_id, route_type) VALUES ('R1', 2); \
+            INSERT INTO trip (route_id, service_id, trip_id, direction_id) VALUES \
+            ('R1', '1', 100, ''), ('R1', '1', 200, '');").unwrap();
+
+        let directions: Vec<Option<u32>> = db.prepare(ROUTE_DIRECTIONS_QUERY).unwrap()
+            .query_map(["R1"], |row| row.get(0)).unwrap()
+            .collect::<rusqlite::Result<_>>().unwrap();
+        assert_eq!(directions, [None]);
+
+        let trips: Vec<u32> = db.prepare(ROUTE_TRIPS_QUERY).unwrap()
+            .query_map(rusqlite::params!["R1", Null], |row| row.get(0)).unwrap()
+            .collect::<rusqlite::Result<_>>().unwrap();
+        assert_eq!(trips, [100, 200]);
+    }
 }
@@ -0,0 +1,294 @@
+use std::collections::BTreeSet;
+
+use chrono::{Datelike, Duration, NaiveDate};
+
+use crate::db::types::{Service, ServiceException, Weekday, WEEKDAY_NAMES};
+
+const DAY_FORMAT: &str = "%-d";
+const MONTH_FORMAT: &str = "%b";
+
+impl Service {
+    // Timetable-style note like "Mon–Fri, not 24 Dec, also 26 Dec".
+    // Tries every weekday pattern and keeps the one listing the fewest dates, then the shortest text.
+    pub fn operating_days(&self) -> String {
+        let running = self.running_dates();
+        if running.is_empty() {
+            return String::from("does not run");
+        }
+
+        // Weekdays only say something about a week or more, shorter services list their dates
+        let last_pattern = if self.validity_dates().len() < 7 { 0 } else { Weekday::all().bits() };
+
+        let mut best: Option<(usize, usize, String)> = None;
+        for bits in 0..=last_pattern {
+            let pattern = Weekday::from_bits_truncate(bits);
+            let (listed, text) = self.describe_with_pattern(pattern, &running);
+            let length = text.chars().count();
+
+            // Ties go to the weekdays of the calendar itself
+            let shorter = match &best {
+                Some((best_listed, best_length, _)) => (listed, length) < (*best_listed, *best_length) ||
+                    ((listed, length) == (*best_listed, *best_length) && pattern == self.operating_weekdays),
+                None => true,
+            };
+            if shorter {
+                best = Some((listed, length, text));
+            }
+        }
+
+        best.unwrap().2
+    }
+
+    // The service moved by whole days, for stops of its trips past midnight
+    pub fn shifted(&self, days: i64) -> Service {
+        Service {
+            start_date: self.start_date + Duration::days(days),
+            end_date: self.end_date + Duration::days(days),
+            operating_weekdays: self.operating_weekdays.shifted(days),
+            exceptions: self.exceptions.iter()
+                .map(|e| ServiceException {
+                    exception_date: e.exception_date + Duration::days(days),
+                    exception_type: e.exception_type,
+                })
+                .collect(),
+        }
+    }
+
+    fn running_dates(&self) -> BTreeSet<NaiveDate> {
+        let mut dates: BTreeSet<NaiveDate> = self.validity_dates().into_iter()
+            .filter(|d| self.is_available(d))
+            .collect();
+        dates.extend(self.exception_dates(1));
+
+        dates
+    }
+
+    fn validity_dates(&self) -> Vec<NaiveDate> {
+        let mut dates = Vec::new();
+        let mut date = self.start_date;
+        while date <= self.end_date {
+            dates.push(date);
+            date += Duration::days(1);
+        }
+
+        dates
+    }
+
+    // The text and how many single dates it lists
+    fn describe_with_pattern(&self, pattern: Weekday, running: &BTreeSet<NaiveDate>) -> (usize, String) {
+        if pattern.is_empty() {
+            return (running.len(), format!("only {}", format_dates(&running.iter().copied().collect::<Vec<_>>())));
+        }
+
+        let not: Vec<NaiveDate> = self.validity_dates().into_iter()
+            .filter(|d| pattern.contains(Weekday::from_date(d)) && !running.contains(d))
+            .collect();
+        let also: Vec<NaiveDate> = running.iter()
+            .filter(|d| !pattern.contains(Weekday::from_date(d)) || !self.is_valid_on(d))
+            .copied()
+            .collect();
+
+        let mut parts = vec![format_weekdays(pattern)];
+        if !not.is_empty() {
+            parts.push(format!("not {}", format_dates(&not)));
+        }
+        if !also.is_empty() {
+            parts.push(format!("also {}", format_dates(&also)));
+        }
+
+        (not.len() + also.len(), parts.join(", "))
+    }
+}
+
+// "daily", "Mon–Fri", "Sat, Sun", "Mon, Wed–Fri"
+pub fn format_weekdays(weekdays: Weekday) -> String {
+    if weekdays == Weekday::all() {
+        return String::from("daily");
+    }
+
+    let mut runs: Vec<(usize, usize)> = Vec::new();
+    for i in (0..WEEKDAY_NAMES.len()).filter(|i| weekdays.contains(Weekday::from_index(*i as u32))) {
+        match runs.last_mut() {
+            Some(run) if run.1 + 1 == i => run.1 = i,
+            _ => runs.push((i, i)),
+        }
+    }
+
+    runs.iter()
+        .map(|&(first, last)| match last - first {
+            0 => WEEKDAY_NAMES[first].to_string(),
+            1 => format!("{}, {}", WEEKDAY_NAMES[first], WEEKDAY_NAMES[last]),
+            _ => format!("{}–{}", WEEKDAY_NAMES[first], WEEKDAY_NAMES[last]),
+        })
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+// Sorted dates as "24–26 Dec, 31 Dec–2 Jan, 3, 5 Feb"
+pub fn format_dates(dates: &[NaiveDate]) -> String {
+    let mut runs: Vec<(NaiveDate, NaiveDate)> = Vec::new();
+    for &date in dates {
+        match runs.last_mut() {
+            Some(run) if run.1 + Duration::days(1) == date => run.1 = date,
+            _ => runs.push((date, date)),
+        }
+    }
+
+    let mut text = String::new();
+    for (i, &(first, last)) in runs.iter().enumerate() {
+        if first == last {
+            text.push_str(&first.format(DAY_FORMAT).to_string());
+        } else if first.month() == last.month() {
+            text.push_str(&format!("{}–{}", first.format(DAY_FORMAT), last.format(DAY_FORMAT)));
+        } else {
+            text.push_str(&format!(
+                "{} {}–{}",
+                first.format(DAY_FORMAT),
+                first.format(MONTH_FORMAT),
+                last.format(DAY_FORMAT),
+            ));
+        }
+
+        // Month is only written once for consecutive runs in the same month
+        let next_in_month = runs.get(i + 1)
+            .is_some_and(|next| next.0.month() == last.month() && next.0.year() == last.year() &&
+                next.1.month() == next.0.month());
+        if next_in_month {
+            text.push_str(", ");
+        } else {
+            text.push_str(&format!(" {}", last.format(MONTH_FORMAT)));
+            if i + 1 < runs.len() {
+                text.push_str(", ");
+            }
+        }
+    }
+
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Datelike, Duration, NaiveDate};
+
+    use crate::db::operating_days::{format_dates, format_weekdays};
+    use crate::db::types::{Service, ServiceException, Weekday};
+
+    // Weekly calendar, or None for a service defined in calendar_dates.txt only
+    type Calendar = Option<(Weekday, &'static str, &'static str)>;
+    // (date, exception_type)
+    type Exceptions = &'static [(&'static str, u8)];
+
+    const WORKDAYS: Weekday = Weekday::from_bits_truncate(0b00011111);
+    const WEEKEND: Weekday = Weekday::from_bits_truncate(0b01100000);
+
+    // (calendar, exceptions, expected)
+    const OPERATING_DAYS: [(Calendar, Exceptions, &str); 9] = [
+        (Some((WORKDAYS, "2026-12-01", "2026-12-31")), &[("2026-12-24", 2), ("2026-12-26", 1)],
+            "Mon–Fri, not 24 Dec, also 26 Dec"),
+        (Some((Weekday::all(), "2026-01-01", "2026-12-31")), &[], "daily"),
+        (Some((WEEKEND, "2026-01-01", "2026-12-31")), &[], "Sat, Sun"),
+        (Some((Weekday::empty(), "2026-01-01", "2026-12-31")), &[], "does not run"),
+        // Removed dates across a month and a year boundary
+        (Some((WORKDAYS, "2026-11-02", "2026-12-31")), &[("2026-11-30", 2), ("2026-12-01", 2), ("2026-12-02", 2)],
+            "Mon–Fri, not 30 Nov–2 Dec"),
+        (Some((WORKDAYS, "2026-12-14", "2027-01-15")), &[("2026-12-25", 2), ("2027-01-01", 2)],
+            "Mon–Fri, not 25 Dec, 1 Jan"),
+        // calendar_dates.txt only
+        (None, &[("2026-12-24", 1)], "only 24 Dec"),
+        (None, &[("2026-03-01", 1), ("2026-03-05", 1)], "only 1, 5 Mar"),
+        (None, &[("2026-03-02", 1), ("2026-03-09", 1), ("2026-03-16", 1), ("2026-03-23", 1)], "Mon"),
+    ];
+
+    // (weekday bits, expected)
+    const WEEKDAYS: [(u8, &str); 7] = [
+        (0b01111111, "daily"),
+        (0b00011111, "Mon–Fri"),
+        (0b01100000, "Sat, Sun"),
+        (0b00011101, "Mon, Wed–Fri"),
+        (0b01000001, "Mon, Sun"),
+        (0b01000000, "Sun"),
+        (0b00000000, ""),
+    ];
+
+    // (sorted dates, expected)
+    const DATES: [(&[&str], &str); 8] = [
+        (&[], ""),
+        (&["2026-03-05"], "5 Mar"),
+        (&["2026-12-24", "2026-12-25", "2026-12-26"], "24–26 Dec"),
+        (&["2026-02-03", "2026-02-05"], "3, 5 Feb"),
+        // Runs across a month and a year boundary
+        (&["2026-11-30", "2026-12-01"], "30 Nov–1 Dec"),
+        (&["2026-12-31", "2027-01-01", "2027-01-02"], "31 Dec–2 Jan"),
+        (&["2026-12-24", "2026-12-25", "2026-12-26", "2026-12-31", "2027-01-01", "2027-01-02", "2027-02-03", "2027-02-05"],
+            "24–26 Dec, 31 Dec–2 Jan, 3, 5 Feb"),
+        // The same month of another year is written again
+        (&["2026-01-05", "2027-01-05"], "5 Jan, 5 Jan"),
+    ];
+
+    fn date(value: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    // Built like fetch_services does
+    fn service(calendar: Calendar, exceptions: &[(&str, u8)]) -> Service {
+        let mut service = match calendar {
+            Some((weekdays, start, end)) => Service::new(date(start), date(end), weekdays),
+            None => {
+                let first = date(exceptions[0].0);
+                Service::new(first, first, Weekday::empty())
+            }
+        };
+        for (exception_date, exception_type) in exceptions {
+            service.add_exception(ServiceException {
+                exception_date: date(exception_date),
+                exception_type: *exception_type,
+            });
+        }
+
+        service
+    }
+
+    #[test]
+    fn operating_days_are_described() {
+        for (calendar, exceptions, expected) in OPERATING_DAYS.iter() {
+            assert_eq!(service(*calendar, exceptions).operating_days(), *expected, "{:?}", exceptions);
+        }
+    }
+
+    #[test]
+    fn calendar_dates_only_workdays_are_a_pattern() {
+        let dates: Vec<String> = (0..56)
+            .map(|d| date("2026-11-02") + Duration::days(d))
+            .filter(|d| d.weekday().num_days_from_monday() < 5 && d != &date("2026-12-24"))
+            .map(|d| d.format("%Y-%m-%d").to_string())
+            .collect();
+        let exceptions: Vec<(&str, u8)> = dates.iter().map(|d| (d.as_str(), 1)).collect();
+
+        assert_eq!(service(None, &exceptions).operating_days(), "Mon–Fri, not 24 Dec");
+    }
+
+    #[test]
+    fn weekdays_are_formatted() {
+        for (bits, expected) in WEEKDAYS.iter() {
+            assert_eq!(format_weekdays(Weekday::from_bits_truncate(*bits)), *expected);
+        }
+    }
+
+    #[test]
+    fn dates_are_formatted() {
+        for (dates, expected) in DATES.iter() {
+            let dates: Vec<NaiveDate> = dates.iter().map(|d| date(d)).collect();
+            assert_eq!(format_dates(&dates), *expected);
+        }
+    }
+
+    #[test]
+    fn shifted_service_runs_the_next_day() {
+        // Fridays in October 2026, not 23 Oct
+        let service = service(Some((Weekday::FRI, "2026-10-01", "2026-10-31")), &[("2026-10-23", 2)]);
+
+        assert_eq!(service.shifted(1).operating_days(), "Sat, not 24 Oct");
+        assert_eq!(Weekday::SUN.shifted(1), Weekday::MON);
+        assert_eq!((Weekday::MON | Weekday::SAT).shifted(-1), Weekday::FRI | Weekday::SUN);
+    }
+}
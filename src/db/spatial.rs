@@ -0,0 +1,76 @@
+use std::collections::HashMap;
+
+use crate::db::types::Station;
+use crate::db::util::degree_deltas;
+
+// Roughly 5.5 km in latitude, a good fit for city-sized queries
+const CELL_SIZE_DEG: f64 = 0.05;
+
+// Uniform lat/lon grid over all stations, built once at startup
+pub struct StationIndex {
+    stations: Vec<Station>,
+    cells: HashMap<(i32, i32), Vec<usize>>,
+}
+
+impl StationIndex {
+    pub fn new(stations: Vec<Station>) -> StationIndex {
+        let mut cells: HashMap<(i32, i32), Vec<usize>> = HashMap::new();
+
+        for (i, s) in stations.iter().enumerate() {
+            if let Some((latitude, longitude)) = s.location() {
+                cells.entry(cell_of(latitude, longitude)).or_default().push(i);
+            }
+        }
+
+        StationIndex { stations, cells }
+    }
+
+    // Stations within radius_km around the given position, nearest first
+    pub fn nearby(&self, latitude: f64, longitude: f64, radius_km: f64) -> Vec<Station> {
+        let (lat_delta, lon_delta) = degree_deltas(latitude, radius_km);
+        let (min_row, min_col) = cell_of(latitude - lat_delta, longitude - lon_delta);
+        let (max_row, max_col) = cell_of(latitude + lat_delta, longitude + lon_delta);
+
+        let mut stations: Vec<Station> = Vec::new();
+        for row in min_row..=max_row {
+            for col in min_col..=max_col {
+                let candidates = match self.cells.get(&(row, col)) {
+                    Some(x) => x,
+                    None => continue,
+                };
+
+                for s in candidates.iter().map(|&i| &self.stations[i]) {
+                    if let Some(distance) = s.distance_to(latitude, longitude).filter(|&d| d <= radius_km) {
+                        stations.push(Station {
+                            distance_km: Some(distance),
+                            ..s.clone()
+                        });
+                    }
+                }
+            }
+        }
+
+        stations.sort_by(|a, b| a.distance_km.partial_cmp(&b.distance_km).unwrap());
+        stations
+    }
+}
+
+fn cell_of(latitude: f64, longitude: f64) -> (i32, i32) {
+    (
+        (latitude / CELL_SIZE_DEG).floor() as i32,
+        (longitude / CELL_SIZE_DEG).floor() as i32,
+    )
+}
+
+// Parses "52.52,13.40" into (latitude, longitude)
+pub fn parse_coordinates(input: &str) -> Option<(f64, f64)> {
+    let mut parts = input.splitn(2, ',');
+    let latitude = parts.next()?.trim().parse::<f64>().ok()?;
+    let longitude = parts.next()?.trim().parse::<f64>().ok()?;
+
+    if (-90.0..=90.0).contains(&latitude) && (-180.0..=180.0).contains(&longitude) {
+        Some((latitude, longitude))
+    } else {
+        None
+    }
+}
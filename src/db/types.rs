@@ -2,25 +2,43 @@ use bitflags::bitflags;
 use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime};
 
 use crate::db::types::BoardType::{ARRIVAL, DEPARTURE};
+use crate::db::util::distance_km;
+use crate::realtime::{AlertInfo, Prediction};
 use crate::db::types::ExceptionType::{ADDED, NONE, REMOVED};
 
 const TIME_FORMAT: &str = "%H:%M";
-
-pub trait WidgetItem<T> {
-    fn to_val(&self) -> T;
-}
+pub const WEEKDAY_NAMES: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
 
 //region Station
+#[derive(Clone)]
 pub struct Station {
     pub stop_id: String,
     pub name: String,
+    // Optional in GTFS for stops which are not locations
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    // Only set for results of a coordinate search
+    pub distance_km: Option<f64>,
 }
 
-impl WidgetItem<String> for Station {
-    fn to_val(&self) -> String {
-        self.stop_id.to_string()
+impl Station {
+    pub fn location(&self) -> Option<(f64, f64)> {
+        self.latitude.zip(self.longitude)
+    }
+
+    pub fn distance_to(&self, latitude: f64, longitude: f64) -> Option<f64> {
+        self.location().map(|(lat, lon)| distance_km(lat, lon, latitude, longitude))
+    }
+
+    pub fn display_name(&self) -> String {
+        match self.distance_km {
+            Some(d) if d < 1.0 => format!("{} ({:.0} m)", self.name, d * 1000.0),
+            Some(d) => format!("{} ({:.1} km)", self.name, d),
+            None => self.name.to_string(),
+        }
     }
 }
+
 //endregion
 
 //region Stop
@@ -30,65 +48,129 @@ pub struct DisplayStop {
     pub trip_id: u32,
     pub short_name: String,
     pub head_sign: String,
+    pub stop_name: String,
+    pub service_date: NaiveDate,
+    // Realtime, delays in minutes
+    pub expected_time: Option<String>,
+    pub arr_delay: Option<i64>,
+    pub dep_delay: Option<i64>,
+    pub cancelled: bool,
+    pub skipped: bool,
+    // ServiceAlerts affecting the stop, route or trip
+    pub alerts: Vec<AlertInfo>,
 }
 
 impl DisplayStop {
-    pub fn from(s: &Stop, dt: NaiveDateTime) -> Self {
+    // Expected time of the board's column, arrival or departure
+    pub fn from(
+        s: &Stop, board_type: &BoardType, service_date: NaiveDate, dt: NaiveDateTime, prediction: &Prediction,
+    ) -> Self {
+        let delay = match board_type {
+            ARRIVAL => prediction.arrival_delay,
+            DEPARTURE => prediction.departure_delay,
+        };
+
         Self {
             arr_time: s.get_adjusted_arr(&dt),
             dep_time: s.get_adjusted_dep(&dt),
             trip_id: s.trip_id,
             short_name: s.short_name.to_string(),
             head_sign: s.head_sign.to_string(),
+            stop_name: s.stop_name.to_string(),
+            service_date,
+            expected_time: delay.and_then(|d| {
+                s.get_adjusted_dt(board_type, &dt)
+                    .map(|t| (t + Duration::seconds(d)).format(TIME_FORMAT).to_string())
+            }),
+            arr_delay: prediction.arrival_delay.map(delay_minutes),
+            dep_delay: prediction.departure_delay.map(delay_minutes),
+            cancelled: prediction.cancelled,
+            skipped: prediction.skipped,
+            alerts: Vec::new(),
         }
     }
-}
 
-impl WidgetItem<u32> for DisplayStop {
-    fn to_val(&self) -> u32 {
-        self.trip_id
+    pub fn delay(&self, board_type: &BoardType) -> Option<i64> {
+        match board_type {
+            ARRIVAL => self.arr_delay,
+            DEPARTURE => self.dep_delay,
+        }
     }
 }
 
+// Nearest full minute, 90 seconds late shows as 2
+fn delay_minutes(seconds: i64) -> i64 {
+    (seconds as f64 / 60.0).round() as i64
+}
+
 pub struct Stop {
-    pub arrival_time: Duration,
-    pub departure_time: Duration,
+    // Either may be missing at stops which are not timepoints
+    pub arrival_time: Option<Duration>,
+    pub departure_time: Option<Duration>,
     pub trip_id: u32,
-    //tmp
+    pub stop_id: String,
+    pub stop_sequence: u32,
+    pub stop_name: String,
     pub short_name: String,
     pub service_id: u16,
     pub head_sign: String,
+    pub route_id: String,
+    pub agency_id: Option<String>,
+    pub route_type: Option<i32>,
 }
 
 impl Stop {
+    // Day the trip started on, times past midnight belong to the previous service day.
+    // Taken from the board's time, arrival and departure may lie on either side of midnight
+    pub fn service_date(&self, board_type: &BoardType, date_time: &NaiveDateTime) -> NaiveDate {
+        date_time.date() - Duration::days(self.board_day_offset(board_type))
+    }
+
+    // Days the board's time lies past the service day, 0 without that time
+    pub fn board_day_offset(&self, board_type: &BoardType) -> i64 {
+        self.get_time_duration(board_type).map_or(0, |t| t.num_days())
+    }
+
+    // Days the stop lies past the service day, 0 without any time
+    pub fn day_offset(&self) -> i64 {
+        self.arrival_time.or(self.departure_time).map_or(0, |t| t.num_days())
+    }
+
     pub fn is_after_adjusted_time(
         &self, board_type: &BoardType, date_time: &NaiveDateTime
     ) -> bool {
-        &self.get_adjusted_dt(board_type, date_time) > date_time
+        self.get_adjusted_dt(board_type, date_time).is_some_and(|dt| &dt > date_time)
     }
 
     pub fn get_adjusted_dt(
         &self,
         board_type: &BoardType,
         base_dt: &NaiveDateTime
-    ) -> NaiveDateTime {
-        let dur_raw = self.get_time_duration(board_type);
+    ) -> Option<NaiveDateTime> {
+        let dur_raw = self.get_time_duration(board_type)?;
         let dur_adjusted = dur_raw - Duration::days(dur_raw.num_days());
 
-        base_dt.date().and_hms(0, 0, 0) + dur_adjusted
+        Some(base_dt.date().and_hms(0, 0, 0) + dur_adjusted)
     }
 
     //todo remove later
     pub fn get_adjusted_arr(&self, base_dt: &NaiveDateTime) -> String {
-        self.get_adjusted_dt(&ARRIVAL, base_dt).format(TIME_FORMAT).to_string()
+        self.format_adjusted(&ARRIVAL, base_dt)
     }
 
     //todo remove later
     pub fn get_adjusted_dep(&self, base_dt: &NaiveDateTime) -> String {
-        self.get_adjusted_dt(&DEPARTURE, base_dt).format(TIME_FORMAT).to_string()
+        self.format_adjusted(&DEPARTURE, base_dt)
     }
 
-    fn get_time_duration(&self, board_type: &BoardType) -> Duration {
+    // Empty if the stop has no such time
+    fn format_adjusted(&self, board_type: &BoardType, base_dt: &NaiveDateTime) -> String {
+        self.get_adjusted_dt(board_type, base_dt)
+            .map(|dt| dt.format(TIME_FORMAT).to_string())
+            .unwrap_or_default()
+    }
+
+    fn get_time_duration(&self, board_type: &BoardType) -> Option<Duration> {
         match board_type {
             ARRIVAL => self.arrival_time,
             DEPARTURE => self.departure_time,
@@ -97,6 +179,81 @@ impl Stop {
 }
 //endregion
 
+//region TripRun
+// A trip found by its number, with what is needed to tell runs apart
+pub struct TripRun {
+    pub trip_id: u32,
+    pub number: String,
+    pub origin: String,
+    pub destination: String,
+    pub departure_time: Duration,
+    pub runs_on_date: bool,
+    // Service days like "Mon–Fri, not 24 Dec"
+    pub operating_days: String,
+}
+
+impl TripRun {
+    pub fn departure(&self) -> String {
+        let time = self.departure_time - Duration::days(self.departure_time.num_days());
+        (NaiveDate::from_ymd(1970, 1, 1).and_hms(0, 0, 0) + time).format(TIME_FORMAT).to_string()
+    }
+}
+
+//endregion
+
+//region Route
+#[derive(Clone)]
+pub struct Route {
+    pub route_id: String,
+    pub short_name: String,
+    pub long_name: String,
+    pub route_type: i32,
+    pub agency: String,
+}
+
+impl Route {
+    pub fn display_name(&self) -> String {
+        match (self.short_name.is_empty(), self.long_name.is_empty()) {
+            (false, false) => format!("{} {}", self.short_name, self.long_name),
+            (false, true) => self.short_name.to_string(),
+            (true, false) => self.long_name.to_string(),
+            (true, true) => self.route_id.to_string(),
+        }
+    }
+}
+
+// Basic and extended GTFS route types
+pub fn route_type_name(route_type: i32) -> &'static str {
+    match route_type {
+        0 | 900..=999 => "Tram",
+        1 | 400..=404 | 406..=499 => "Subway",
+        12 | 405 => "Monorail",
+        2 | 100..=199 => "Rail",
+        3 | 200..=299 | 700..=799 => "Bus",
+        4 | 1000..=1099 | 1200..=1299 => "Ferry",
+        5 => "Cable tram",
+        6 | 1300..=1399 => "Aerial lift",
+        7 | 1400..=1499 => "Funicular",
+        11 | 800..=899 => "Trolleybus",
+        _ => "Other",
+    }
+}
+//endregion
+
+//region Agency
+// Composition of the feed by agency, the trips and departures running on one date
+pub struct AgencyStats {
+    pub agency_id: String,
+    pub name: String,
+    pub routes: u32,
+    pub trips: u32,
+    pub trips_on_date: u32,
+    pub stops: u32,
+    pub departures: u32,
+}
+
+//endregion
+
 //region Service availability
 bitflags! {
     pub struct Weekday: u8 {
@@ -131,6 +288,24 @@ impl Weekday {
     pub fn from_index(index: u32) -> Weekday {
         Weekday::from_bits_truncate(2_i32.pow(index) as u8)
     }
+
+    pub fn from_date(date: &NaiveDate) -> Weekday {
+        Weekday::from_index(date.weekday().num_days_from_monday())
+    }
+
+    // Weekdays the given number of days later, Fri shifted by 1 is Sat
+    pub fn shifted(&self, days: i64) -> Weekday {
+        let n = days.rem_euclid(7) as u32;
+        let bits = u32::from(self.bits());
+        Weekday::from_bits_truncate(((bits << n) | (bits >> (7 - n))) as u8)
+    }
+
+    pub fn short_names(&self) -> Vec<&'static str> {
+        WEEKDAY_NAMES.iter().enumerate()
+            .filter(|(i, _)| self.contains(Weekday::from_index(*i as u32)))
+            .map(|(_, name)| *name)
+            .collect()
+    }
 }
 
 enum ExceptionType {
@@ -139,11 +314,13 @@ enum ExceptionType {
     REMOVED,
 }
 
+#[derive(Copy, Clone)]
 pub enum BoardType {
     ARRIVAL,
     DEPARTURE,
 }
 
+#[derive(Clone)]
 pub struct Service {
     pub start_date: NaiveDate,
     pub end_date: NaiveDate,
@@ -151,6 +328,7 @@ pub struct Service {
     pub exceptions: Vec<ServiceException>,
 }
 
+#[derive(Clone)]
 pub struct ServiceException {
     pub exception_date: NaiveDate,
     pub exception_type: u8,
@@ -168,31 +346,160 @@ impl Service {
         }
     }
 
+    // Dates only defined by exceptions widen the validity range
+    pub fn add_exception(&mut self, exception: ServiceException) {
+        if self.operating_weekdays.is_empty() {
+            self.start_date = self.start_date.min(exception.exception_date);
+            self.end_date = self.end_date.max(exception.exception_date);
+        }
+        self.exceptions.push(exception);
+    }
+
+    pub fn is_valid_on(&self, date: &NaiveDate) -> bool {
+        &self.start_date <= date && date <= &self.end_date
+    }
+
     pub fn is_available(&self, date: &NaiveDate) -> bool {
-        let weekday = Weekday::from_index(date.weekday().num_days_from_monday());
+        let weekday = Weekday::from_date(date);
 
         match self.get_exception_type_for_date(date) {
             ADDED => true,
-            NONE => !(self.operating_weekdays & weekday).is_empty(),
+            NONE => self.is_valid_on(date) && !(self.operating_weekdays & weekday).is_empty(),
             _ => false
         }
     }
 
     fn get_exception_type_for_date(&self, date: &NaiveDate) -> ExceptionType {
         let exception = self.exceptions.iter()
-            .filter(|e| e.exception_date.eq(date))
-            .next();
+            .find(|e| e.exception_date.eq(date));
 
         if let Some(x) = exception {
             match x.exception_type {
                 1 => ADDED,
                 2 => REMOVED,
-                _ => panic!("Unexpected exception type {}!", x.exception_type),
+                // Invalid types are ignored here and reported by the validator
+                _ => NONE,
             }
         } else {
             NONE
         }
     }
 
+    // Exception dates of the given type (1: added, 2: removed), sorted
+    pub fn exception_dates(&self, exception_type: u8) -> Vec<NaiveDate> {
+        let mut dates: Vec<NaiveDate> = self.exceptions.iter()
+            .filter(|e| e.exception_type == exception_type)
+            .map(|e| e.exception_date)
+            .collect();
+        dates.sort();
+
+        dates
+    }
 }
 //endregion
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use chrono::{Duration, NaiveDate};
+
+    use crate::db::calendar::ServiceDays;
+    use crate::db::types::{BoardType, Service, ServiceException, Stop, Weekday};
+
+    // Weekly calendar, or None for a service defined in calendar_dates.txt only
+    type Calendar = Option<(Weekday, &'static str, &'static str)>;
+    // (date, exception_type)
+    type Exceptions = &'static [(&'static str, u8)];
+
+    // (calendar, exceptions, date, expected)
+    const CASES: [(Calendar, Exceptions, &str, bool); 15] = [
+        // Validity range edges, Wed 2026-01-07 to Fri 2026-01-30
+        (Some((Weekday::all(), "2026-01-07", "2026-01-30")), &[], "2026-01-06", false),
+        (Some((Weekday::all(), "2026-01-07", "2026-01-30")), &[], "2026-01-07", true),
+        (Some((Weekday::all(), "2026-01-07", "2026-01-30")), &[], "2026-01-30", true),
+        (Some((Weekday::all(), "2026-01-07", "2026-01-30")), &[], "2026-01-31", false),
+        // Weekday pattern, Mon-Fri
+        (Some((WORKDAYS, "2026-01-07", "2026-01-30")), &[], "2026-01-09", true),
+        (Some((WORKDAYS, "2026-01-07", "2026-01-30")), &[], "2026-01-10", false),
+        // Added and removed exceptions
+        (Some((WORKDAYS, "2026-01-07", "2026-01-30")), &[("2026-01-10", 1)], "2026-01-10", true),
+        (Some((WORKDAYS, "2026-01-07", "2026-01-30")), &[("2026-01-14", 2)], "2026-01-14", false),
+        (Some((WORKDAYS, "2026-01-07", "2026-01-30")), &[("2026-01-14", 2)], "2026-01-15", true),
+        // Added outside the validity range
+        (Some((WORKDAYS, "2026-01-07", "2026-01-30")), &[("2026-02-07", 1)], "2026-02-07", true),
+        // Invalid exception types are ignored
+        (Some((WORKDAYS, "2026-01-07", "2026-01-30")), &[("2026-01-14", 3)], "2026-01-14", true),
+        // calendar_dates.txt only
+        (None, &[("2026-03-01", 1), ("2026-03-05", 1)], "2026-03-01", true),
+        (None, &[("2026-03-01", 1), ("2026-03-05", 1)], "2026-03-05", true),
+        (None, &[("2026-03-01", 1), ("2026-03-05", 1)], "2026-03-03", false),
+        (None, &[("2026-03-01", 1), ("2026-03-05", 1)], "2026-02-28", false),
+    ];
+
+    const WORKDAYS: Weekday = Weekday::from_bits_truncate(0b00011111);
+
+    fn date(value: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    // Built like fetch_services does
+    fn service(calendar: Calendar, exceptions: &[(&str, u8)]) -> Service {
+        let mut service = match calendar {
+            Some((weekdays, start, end)) => Service::new(date(start), date(end), weekdays),
+            None => {
+                let first = date(exceptions[0].0);
+                Service::new(first, first, Weekday::empty())
+            }
+        };
+        for (exception_date, exception_type) in exceptions {
+            service.add_exception(ServiceException {
+                exception_date: date(exception_date),
+                exception_type: *exception_type,
+            });
+        }
+
+        service
+    }
+
+    #[test]
+    fn service_is_available() {
+        for (calendar, exceptions, day, expected) in CASES.iter() {
+            let service = service(*calendar, exceptions);
+            assert_eq!(service.is_available(&date(day)), *expected, "{} with {:?}", day, exceptions);
+        }
+    }
+
+    #[test]
+    fn service_days_match_is_available() {
+        for (calendar, exceptions, day, expected) in CASES.iter() {
+            let services: HashMap<u16, Service> = vec![(1, service(*calendar, exceptions))].into_iter().collect();
+            let days = ServiceDays::new(&services);
+            assert_eq!(days.is_active(1, &date(day)), *expected, "{} with {:?}", day, exceptions);
+        }
+    }
+
+    #[test]
+    fn service_date_follows_board_time() {
+        // Arrives 23:58 and leaves 00:02 after midnight of the service day
+        let stop = Stop {
+            arrival_time: Some(Duration::minutes(23 * 60 + 58)),
+            departure_time: Some(Duration::minutes(24 * 60 + 2)),
+            trip_id: 100,
+            stop_id: String::from("A"),
+            stop_sequence: 2,
+            stop_name: String::new(),
+            short_name: String::new(),
+            service_id: 1,
+            head_sign: String::new(),
+            route_id: String::new(),
+            agency_id: None,
+            route_type: None,
+        };
+
+        let departures = date("2026-10-20").and_hms(0, 0, 0);
+        assert_eq!(stop.service_date(&BoardType::DEPARTURE, &departures), date("2026-10-19"));
+        let arrivals = date("2026-10-19").and_hms(23, 0, 0);
+        assert_eq!(stop.service_date(&BoardType::ARRIVAL, &arrivals), date("2026-10-19"));
+    }
+}
@@ -1,16 +1,27 @@
-use chrono::{Duration, NaiveDate, ParseResult};
-use regex::Regex;
+use chrono::{NaiveDate, ParseResult};
 
 const DATE_FORMAT: &str = "%Y%m%d";
+const EARTH_RADIUS_KM: f64 = 6371.0;
 
 pub fn str_to_date(val: String) -> ParseResult<NaiveDate> {
     NaiveDate::parse_from_str(&val, DATE_FORMAT)
 }
 
-pub fn str_to_dur(regex: &Regex, val: String) -> ParseResult<Duration> {
-    let caps = regex.captures(&val).unwrap();
-    Ok(Duration::hours(caps["hours"].parse::<i64>().unwrap()) +
-        Duration::minutes(caps["minutes"].parse::<i64>().unwrap()) +
-        Duration::seconds(caps["seconds"].parse::<i64>().unwrap())
-    )
+// Great-circle distance between two positions (haversine)
+pub fn distance_km(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+    let d_lat = (lat2 - lat1).to_radians();
+    let d_lon = (lon2 - lon1).to_radians();
+
+    let a = (d_lat / 2.0).sin().powi(2) +
+        lat1.to_radians().cos() * lat2.to_radians().cos() * (d_lon / 2.0).sin().powi(2);
+
+    2.0 * EARTH_RADIUS_KM * a.sqrt().asin()
+}
+
+// Latitude and longitude span covering distance_km around the given latitude
+pub fn degree_deltas(latitude: f64, distance_km: f64) -> (f64, f64) {
+    let lat_delta = (distance_km / EARTH_RADIUS_KM).to_degrees();
+    let lon_delta = lat_delta / latitude.to_radians().cos().max(0.01);
+
+    (lat_delta, lon_delta)
 }
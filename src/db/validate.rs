@@ -0,0 +1,234 @@
+use std::error::Error;
+
+use rusqlite::{Connection, OpenFlags, Result};
+
+use crate::db::calendar::ServiceDays;
+use crate::db::fetch_services;
+
+// Findings of one check beyond this are summed up in a single finding
+const MAX_FINDINGS_PER_CHECK: usize = 100;
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum Severity {
+    ERROR,
+    WARNING,
+}
+
+impl Severity {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Severity::ERROR => "error",
+            Severity::WARNING => "warning",
+        }
+    }
+}
+
+pub struct Finding {
+    pub severity: Severity,
+    pub check: &'static str,
+    // Offending row, e.g. "trip 100"
+    pub subject: String,
+    pub message: String,
+}
+
+//region Checks
+// Each query selects the subject and message of its findings
+const CHECKS: [(&str, Severity, &str); 12] = [
+    ("trip_route", Severity::ERROR, "SELECT 'trip ' || t.trip_id, 'unknown route ''' || t.route_id || '''' \
+        FROM trip t \
+        WHERE NOT EXISTS (SELECT 1 FROM route r WHERE r.route_id = t.route_id);"),
+    ("route_agency", Severity::ERROR, "SELECT 'route ' || r.route_id, 'unknown agency ''' || r.agency_id || '''' \
+        FROM route r \
+        WHERE r.agency_id IS NOT NULL AND r.agency_id <> '' \
+        AND NOT EXISTS (SELECT 1 FROM agency a WHERE a.agency_id = r.agency_id);"),
+    ("trip_service", Severity::ERROR, "SELECT 'trip ' || t.trip_id, 'unknown service ''' || t.service_id || '''' \
+        FROM trip t \
+        WHERE NOT EXISTS (SELECT 1 FROM service s WHERE s.service_id = t.service_id) \
+        AND NOT EXISTS (SELECT 1 FROM service_exception e WHERE e.service_id = t.service_id);"),
+    ("stop_time_trip", Severity::ERROR, "SELECT 'stop_time ' || st.trip_id || '/' || st.stop_sequence, 'unknown trip' \
+        FROM stop_time st \
+        WHERE NOT EXISTS (SELECT 1 FROM trip t WHERE t.trip_id = st.trip_id);"),
+    ("stop_time_stop", Severity::ERROR, "SELECT 'stop_time ' || st.trip_id || '/' || st.stop_sequence, \
+        'unknown stop ''' || st.stop_id || '''' \
+        FROM stop_time st \
+        WHERE NOT EXISTS (SELECT 1 FROM stop s WHERE s.stop_id = st.stop_id);"),
+    // Times are seconds since midnight of the service day
+    ("stop_time_text", Severity::ERROR, "SELECT 'stop_time ' || trip_id || '/' || stop_sequence, \
+        'times stored as text, run the migrate command' \
+        FROM stop_time \
+        WHERE typeof(arrival_time) = 'text' OR typeof(departure_time) = 'text';"),
+    ("stop_time_order", Severity::ERROR, "SELECT 'stop_time ' || trip_id || '/' || stop_sequence, \
+        CASE WHEN departure_time < arrival_time \
+            THEN printf('departs %02d:%02d before it arrives %02d:%02d', \
+                departure_time / 3600, departure_time / 60 % 60, arrival_time / 3600, arrival_time / 60 % 60) \
+            ELSE printf('arrives %02d:%02d before the previous stop departs %02d:%02d', \
+                arrival_time / 3600, arrival_time / 60 % 60, previous_departure / 3600, previous_departure / 60 % 60) \
+        END \
+        FROM (SELECT trip_id, stop_sequence, arrival_time, departure_time, \
+            LAG(departure_time) OVER (PARTITION BY trip_id ORDER BY stop_sequence) AS previous_departure \
+            FROM stop_time \
+            WHERE typeof(arrival_time) <> 'text' AND typeof(departure_time) <> 'text') \
+        WHERE departure_time < arrival_time OR arrival_time < previous_departure;"),
+    ("stop_sequence_gap", Severity::WARNING, "SELECT 'trip ' || trip_id, \
+        COUNT(*) || ' gap(s) in stop_sequence, first before ' || MIN(stop_sequence) \
+        FROM (SELECT trip_id, stop_sequence, \
+            LAG(stop_sequence) OVER (PARTITION BY trip_id ORDER BY stop_sequence) AS previous_sequence \
+            FROM stop_time) \
+        WHERE stop_sequence <> previous_sequence + 1 \
+        GROUP BY trip_id;"),
+    ("service_id", Severity::ERROR, "SELECT 'service ' || service_id, 'service_id is not a number' \
+        FROM (SELECT service_id FROM service UNION SELECT service_id FROM service_exception) \
+        WHERE typeof(service_id) <> 'integer';"),
+    // Dates are YYYYMMDD, a modifier makes date() normalize days past the end of the month
+    ("service_date", Severity::ERROR, "SELECT 'service ' || service_id, 'invalid ' || field || ' ''' || value || '''' \
+        FROM (SELECT service_id, 'start_date' AS field, start_date AS value FROM service \
+            UNION ALL SELECT service_id, 'end_date', end_date FROM service \
+            UNION ALL SELECT service_id, 'service_date', service_date FROM service_exception) \
+        WHERE length(value) <> 8 OR value GLOB '*[^0-9]*' \
+        OR date(substr(value, 1, 4) || '-' || substr(value, 5, 2) || '-' || substr(value, 7, 2), '+0 days') \
+            IS NOT substr(value, 1, 4) || '-' || substr(value, 5, 2) || '-' || substr(value, 7, 2);"),
+    ("exception_type", Severity::ERROR, "SELECT 'service ' || service_id, \
+        'invalid exception_type ' || exception_type || ' on ' || service_date || ', expected 1 or 2' \
+        FROM service_exception \
+        WHERE exception_type NOT IN (1, 2);"),
+    ("unused_stop", Severity::WARNING, "SELECT 'stop ' || s.stop_id, 'no stop times at ' || s.name \
+        FROM stop s \
+        WHERE NOT EXISTS (SELECT 1 FROM stop_time st WHERE st.stop_id = s.stop_id);"),
+];
+//endregion
+
+// Integrity and plausibility problems of the feed, errors first.
+// Runs on its own read-only connection, feeds GTFSDatabase fails to load are checked as well
+pub fn validate(db_path: &str) -> Result<Vec<Finding>, Box<dyn Error>> {
+    let db = Connection::open_with_flags(db_path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    let mut findings = Vec::new();
+
+    for (check, severity, query) in CHECKS.iter() {
+        let mut stmt = db.prepare(query)?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        let rows: Vec<(String, String)> = rows.collect::<Result<_>>()?;
+        push_findings(&mut findings, check, *severity, rows);
+    }
+
+    // The calendar is resolved in Rust, services are checked on the loaded one
+    match fetch_services(&db) {
+        Ok(services) => {
+            let service_days = ServiceDays::new(&services);
+            let mut never_running: Vec<&u16> = services.keys()
+                .filter(|id| !service_days.is_ever_active(**id))
+                .collect();
+            never_running.sort();
+            let rows = never_running.iter()
+                .map(|id| (format!("service {}", id), String::from("never runs")))
+                .collect();
+            push_findings(&mut findings, "service_never_runs", Severity::WARNING, rows);
+        }
+        Err(e) => {
+            let rows = vec![(String::from("calendar"), format!("services can't be loaded: {}", e))];
+            push_findings(&mut findings, "service_load", Severity::ERROR, rows);
+        }
+    }
+
+    findings.sort_by_key(|f| f.severity != Severity::ERROR);
+    Ok(findings)
+}
+
+fn push_findings(
+    findings: &mut Vec<Finding>, check: &'static str, severity: Severity, rows: Vec<(String, String)>,
+) {
+    let total = rows.len();
+    findings.extend(rows.into_iter()
+        .take(MAX_FINDINGS_PER_CHECK)
+        .map(|(subject, message)| Finding { severity, check, subject, message }));
+
+    if total > MAX_FINDINGS_PER_CHECK {
+        findings.push(Finding {
+            severity,
+            check,
+            subject: String::new(),
+            message: format!("{} more", total - MAX_FINDINGS_PER_CHECK),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rusqlite::Connection;
+
+    use crate::db::validate::validate;
+
+    const SCHEMA_SQL: &str = include_str!("../../scripts/schema.sql");
+
+    // A feed without findings, each case below breaks it in one place
+    const FIXTURE_SQL: &str = "\
+        INSERT INTO service VALUES (1, 1, 1, 1, 1, 1, 1, 1, '20260101', '20261231'); \
+        INSERT INTO agency (agency_id, name) VALUES (1, 'DB'); \
+        INSERT INTO route (route_id, agency_id, short_name, long_name, route_type) VALUES ('R1', 1, 'ICE', '', 101); \
+        INSERT INTO stop (stop_id, name, latitude, longitude) VALUES \
+            ('A', 'Berlin Hbf', 52.525, 13.369), ('B', 'Hamburg Hbf', 53.553, 10.007); \
+        INSERT INTO trip (route_id, service_id, trip_id, headsign, short_name) VALUES ('R1', '1', 100, 'Hamburg Hbf', '598'); \
+        INSERT INTO stop_time (trip_id, arrival_time, departure_time, stop_id, stop_sequence) VALUES \
+            (100, 28800, 29100, 'A', 1), (100, 35100, 35100, 'B', 2); \
+        PRAGMA foreign_keys = OFF;";
+
+    // (check, statement breaking the fixture, subject of the finding)
+    const CASES: [(&str, &str, &str); 14] = [
+        ("trip_route", "INSERT INTO trip (route_id, service_id, trip_id) VALUES ('R9', '1', 101);", "trip 101"),
+        ("route_agency", "INSERT INTO route (route_id, agency_id, route_type) VALUES ('R2', 9, 106);", "route R2"),
+        ("trip_service", "INSERT INTO trip (route_id, service_id, trip_id) VALUES ('R1', '7', 102);", "trip 102"),
+        ("stop_time_trip", "INSERT INTO stop_time VALUES (999, 0, 0, 'A', 1, 0, 0, NULL);", "stop_time 999/1"),
+        ("stop_time_stop", "INSERT INTO stop_time VALUES (100, 36000, 36000, 'Z', 3, 0, 0, NULL);", "stop_time 100/3"),
+        ("stop_time_text", "UPDATE stop_time SET arrival_time = '08:00:00' WHERE stop_sequence = 1;", "stop_time 100/1"),
+        ("stop_time_order", "UPDATE stop_time SET departure_time = 28000 WHERE stop_sequence = 1;", "stop_time 100/1"),
+        ("stop_time_order", "UPDATE stop_time SET arrival_time = 29000 WHERE stop_sequence = 2;", "stop_time 100/2"),
+        ("stop_sequence_gap", "UPDATE stop_time SET stop_sequence = 3 WHERE stop_sequence = 2;", "trip 100"),
+        ("service_id", "INSERT INTO service_exception VALUES ('x', '20260105', 1);", "service x"),
+        ("service_date", "UPDATE service SET end_date = '20260231';", "service 1"),
+        ("exception_type", "INSERT INTO service_exception VALUES (1, '20260105', 3);", "service 1"),
+        ("unused_stop", "INSERT INTO stop (stop_id, name) VALUES ('C', 'Hannover Hbf');", "stop C"),
+        ("service_never_runs", "INSERT INTO service VALUES (2, 0, 0, 0, 0, 0, 0, 0, '20260101', '20261231');",
+            "service 2"),
+    ];
+
+    // Findings as (check, subject) of the fixture with the statement applied
+    // Tests run in parallel, each passes its own name for the database file
+    fn findings(name: &str, statement: &str) -> Vec<(String, String)> {
+        let path = std::env::temp_dir().join(format!("gtfs-viewer-validate-{}-{}.db", std::process::id(), name));
+        let path = path.to_str().unwrap();
+        // Left over if a previous run failed
+        let _ = std::fs::remove_file(path);
+        let fixture = Connection::open(path).unwrap();
+        fixture.execute_batch(SCHEMA_SQL).unwrap();
+        fixture.execute_batch(FIXTURE_SQL).unwrap();
+        fixture.execute_batch(statement).unwrap();
+        drop(fixture);
+
+        let findings = validate(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+        findings.into_iter().map(|f| (f.check.to_string(), f.subject)).collect()
+    }
+
+    #[test]
+    fn valid_feed_has_no_findings() {
+        assert_eq!(findings("valid", ""), []);
+    }
+
+    #[test]
+    fn each_check_finds_its_problem() {
+        for (check, statement, subject) in CASES.iter() {
+            let findings = findings(check, statement);
+            assert!(
+                findings.contains(&(check.to_string(), subject.to_string())),
+                "{}: {:?}", check, findings,
+            );
+        }
+    }
+
+    #[test]
+    fn unloadable_calendar_is_an_error() {
+        let findings = findings("service_load", "UPDATE service SET start_date = '2026';");
+
+        assert!(findings.contains(&(String::from("service_date"), String::from("service 1"))));
+        assert!(findings.contains(&(String::from("service_load"), String::from("calendar"))));
+    }
+}
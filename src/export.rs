@@ -0,0 +1,206 @@
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::cli::{csv_line, Record, write_records};
+use crate::config::OutputFormat;
+use crate::db::grid::RouteGrid;
+use crate::db::types::{BoardType, DisplayStop};
+use crate::ui::board::Board;
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+const TIME_FORMAT: &str = "%H:%M";
+const POSTER_WIDTH: usize = 72;
+
+#[derive(Copy, Clone)]
+pub enum ExportFormat {
+    CSV,
+    JSON,
+    // Formatted like a printed departure poster
+    TEXT,
+}
+
+impl ExportFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::CSV => "csv",
+            ExportFormat::JSON => "json",
+            ExportFormat::TEXT => "txt",
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ExportRow {
+    trip_id: u32,
+    number: String,
+    destination: String,
+    arrival: String,
+    departure: String,
+    expected: Option<String>,
+    delay: Option<i64>,
+    cancelled: bool,
+}
+
+impl ExportRow {
+    fn from(stop: &DisplayStop, board_type: BoardType) -> Self {
+        Self {
+            trip_id: stop.trip_id,
+            number: stop.short_name.to_string(),
+            destination: stop.head_sign.to_string(),
+            arrival: stop.arr_time.to_string(),
+            departure: stop.dep_time.to_string(),
+            expected: stop.expected_time.clone(),
+            delay: stop.delay(&board_type),
+            cancelled: stop.cancelled,
+        }
+    }
+
+    fn time(&self, board_type: BoardType) -> &str {
+        match board_type {
+            BoardType::ARRIVAL => &self.arrival,
+            BoardType::DEPARTURE => &self.departure,
+        }
+    }
+
+    fn remarks(&self) -> String {
+        match (self.cancelled, &self.expected, self.delay) {
+            (true, _, _) => String::from("cancelled"),
+            (false, Some(expected), Some(delay)) if delay != 0 => format!("{} ({:+})", expected, delay),
+            _ => String::new(),
+        }
+    }
+}
+
+impl Record for ExportRow {
+    fn header() -> &'static [&'static str] {
+        &["trip_id", "number", "destination", "arrival", "departure", "expected", "delay", "cancelled"]
+    }
+
+    fn fields(&self) -> Vec<String> {
+        vec![
+            self.trip_id.to_string(),
+            self.number.to_string(),
+            self.destination.to_string(),
+            self.arrival.to_string(),
+            self.departure.to_string(),
+            self.expected.clone().unwrap_or_default(),
+            self.delay.map_or(String::new(), |d| d.to_string()),
+            self.cancelled.to_string(),
+        ]
+    }
+}
+
+// Board as shown, including the selection it was fetched for
+#[derive(Serialize)]
+struct BoardExport<'a> {
+    station: &'a str,
+    board_type: &'static str,
+    date: String,
+    from: String,
+    until: Option<String>,
+    rows: Vec<ExportRow>,
+}
+
+// Writes the board to "<station>_<date>_<time>_<type>.<ext>" in dir and returns the path
+pub fn write_board(board: &Board, format: ExportFormat, dir: &str) -> Result<PathBuf, Box<dyn Error>> {
+    let rows: Vec<ExportRow> = board.data.items.iter()
+        .map(|s| ExportRow::from(s, board.board_type))
+        .collect();
+    let export = BoardExport {
+        station: &board.station,
+        board_type: match board.board_type {
+            BoardType::ARRIVAL => "arrivals",
+            BoardType::DEPARTURE => "departures",
+        },
+        date: board.date_time.format(DATE_FORMAT).to_string(),
+        from: board.date_time.format(TIME_FORMAT).to_string(),
+        until: rows.last().map(|r| r.time(board.board_type).to_string()),
+        rows,
+    };
+
+    fs::create_dir_all(dir)?;
+    let path = Path::new(dir).join(format!(
+        "{}_{}_{}.{}",
+        file_name(export.station),
+        board.date_time.format("%Y%m%d_%H%M"),
+        export.board_type,
+        format.extension(),
+    ));
+    let mut out = BufWriter::new(File::create(&path)?);
+
+    match format {
+        ExportFormat::CSV => write_records(&mut out, &export.rows, OutputFormat::CSV)?,
+        ExportFormat::JSON => {
+            serde_json::to_writer_pretty(&mut out, &export)?;
+            writeln!(out)?;
+        }
+        ExportFormat::TEXT => write_poster(&mut out, &export, board.board_type)?,
+    }
+    out.flush()?;
+
+    Ok(path)
+}
+
+// Writes the grid to "<route>.csv" in dir, one row per stop and one column per trip
+pub fn write_route_grid(grid: &RouteGrid, dir: &str) -> Result<PathBuf, Box<dyn Error>> {
+    fs::create_dir_all(dir)?;
+    let path = Path::new(dir).join(format!("{}.csv", file_name(&grid.route_name)));
+    let mut out = BufWriter::new(File::create(&path)?);
+
+    let header: Vec<String> = ["stop_id", "stop"].iter().map(|h| h.to_string())
+        .chain(grid.trips.iter().map(|t| t.number.to_string()))
+        .collect();
+    writeln!(out, "{}", csv_line(&header))?;
+
+    for (stop, times) in grid.stops.iter().zip(grid.times.iter()) {
+        let fields: Vec<String> = vec![stop.stop_id.to_string(), stop.name.to_string()].into_iter()
+            .chain(times.iter().map(|t| t.clone().unwrap_or_default()))
+            .collect();
+        writeln!(out, "{}", csv_line(&fields))?;
+    }
+    out.flush()?;
+
+    Ok(path)
+}
+
+fn write_poster(
+    out: &mut dyn Write, export: &BoardExport, board_type: BoardType,
+) -> Result<(), Box<dyn Error>> {
+    let (title, time_header, place_header) = match board_type {
+        BoardType::ARRIVAL => ("Arrivals", "Arr.", "From"),
+        BoardType::DEPARTURE => ("Departures", "Dep.", "Destination"),
+    };
+
+    writeln!(out, "{}", "=".repeat(POSTER_WIDTH))?;
+    writeln!(out, "{}", export.station)?;
+    writeln!(
+        out, "{}  {}  {} - {}",
+        title, export.date, export.from, export.until.as_deref().unwrap_or(""),
+    )?;
+    writeln!(out, "{}", "=".repeat(POSTER_WIDTH))?;
+    writeln!(out, "{:<6} {:<10} {:<32} Remarks", time_header, "Train", place_header)?;
+    writeln!(out, "{}", "-".repeat(POSTER_WIDTH))?;
+
+    for row in &export.rows {
+        let line = format!(
+            "{:<6} {:<10} {:<32} {}",
+            row.time(board_type), row.number, row.destination, row.remarks(),
+        );
+        writeln!(out, "{}", line.trim_end())?;
+    }
+
+    Ok(())
+}
+
+// Station names may contain characters not allowed in file names
+fn file_name(station: &str) -> String {
+    let name: String = station.chars()
+        .map(|c| if c.is_alphanumeric() || c == '-' { c } else { '_' })
+        .collect();
+
+    if name.is_empty() { String::from("board") } else { name }
+}
@@ -1,7 +1,6 @@
 use crossterm::event::{KeyCode, KeyEvent};
 
-use crate::ui::{WidgetState, WidgetData};
-use crate::db::types::WidgetItem;
+use crate::ui::{WidgetData, WidgetItem, WidgetState};
 
 pub trait KeyHandler {
     fn handle_key(&mut self, event: &KeyEvent);
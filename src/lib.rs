@@ -0,0 +1,10 @@
+//! Timetable data model and queries of gtfs-viewer, shared by the TUI and other tools.
+//!
+//! [`db::GTFSDatabase`] opens an imported feed (see `scripts/gtfs-db.sh`) and answers the
+//! station, board, trip and service lookups. [`realtime`] decodes GTFS-Realtime feeds and
+//! applies them to scheduled stops.
+
+#![allow(clippy::upper_case_acronyms)]
+
+pub mod db;
+pub mod realtime;
@@ -1,26 +1,80 @@
+#![allow(clippy::upper_case_acronyms)]
+
+use std::env;
 use std::error::Error;
 use std::io::stdout;
+use std::time::Duration;
 
-use crossterm::event::{Event, KeyCode, read};
+use crossterm::event::{Event, KeyCode, poll, read};
 use crossterm::execute;
 use crossterm::terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen};
 use tui::backend::CrosstermBackend;
 use tui::layout::{Constraint, Direction, Layout};
 use tui::Terminal;
 
-use crate::ui::App;
+// Data model and queries live in the library, the binary only adds the frontends
+use gtfs_viewer::{db, realtime};
+
+use crate::ui::{App, COORDINATE_PREFIX};
 use crate::db::GTFSDatabase;
+use crate::config::{Command, Config};
+use crate::realtime::{RealtimeSource, spawn_poller};
+use crate::realtime::record::{list_snapshots, spawn_replay};
 
 mod handler;
 mod ui;
-mod db;
+mod config;
+mod cli;
+mod server;
+mod export;
 
-// TODO replace later with config field
-const DB_PATH: &str = "scripts/data.db";
+// Redraw interval while waiting for input, picks up realtime updates
+const TICK: Duration = Duration::from_millis(250);
 
 fn main() -> Result<(), Box<dyn Error>> {
+    let config = Config::from_args(env::args().skip(1))?;
+
+    // Commands working on the database without loading it
+    match &config.command {
+        // The only command writing to the database
+        Command::MIGRATE => return db::migrate(&config.db_path, cli::print_progress),
+        // Also checks feeds which fail to load
+        Command::VALIDATE(args) => return cli::validate::run(&config.db_path, args),
+        _ => {}
+    }
+
     // DB
-    let db = GTFSDatabase::new(DB_PATH)?;
+    let db = GTFSDatabase::new(&config.db_path, cli::print_progress)?;
+
+    // Non-interactive commands print and exit
+    match &config.command {
+        Command::BOARD(args) => return cli::board::run(&db, args),
+        Command::TRIP(args) => return cli::trip::run(&db, args),
+        Command::STATIONS(args) => return cli::stations::run(&db, args),
+        Command::SERVE(args) => return server::serve(&db, args),
+        Command::POSTER(args) => return cli::poster::run(&db, args),
+        Command::DIFF(args) => return cli::diff::run(&db, args),
+        Command::TUI | Command::MIGRATE | Command::VALIDATE(_) => {}
+    }
+
+    // Compare mode, diffed before the UI takes the database
+    let changes = match &config.compare {
+        Some(path) => Some(db::diff::diff(&GTFSDatabase::new(path, cli::print_progress)?, &db)?),
+        None => None,
+    };
+
+    // Realtime
+    let realtime = match (&config.realtime, &config.replay) {
+        (Some(r), _) => Some(spawn_poller(RealtimeSource::from(r), config.poll_interval, config.record.clone())),
+        (None, Some(dir)) => {
+            let snapshots = list_snapshots(dir)?;
+            if snapshots.is_empty() {
+                return Err(format!("No recorded snapshots in '{}'", dir).into());
+            }
+            Some(spawn_replay(snapshots, config.replay_speed))
+        }
+        (None, None) => None,
+    };
 
     // UI
     let mut stdout = stdout();
@@ -31,6 +85,15 @@ fn main() -> Result<(), Box<dyn Error>> {
     let mut terminal = Terminal::new(backend)?;
 
     let mut app = App::new(db);
+    app.replay = config.replay.is_some();
+    app.export_dir = config.export_dir.to_string();
+    if let (Some(path), Some(changes)) = (&config.compare, changes) {
+        app.diff.set_changes(path, changes);
+        app.diff.visible = true;
+    }
+    if let Some((lat, lon)) = config.near {
+        app.search.input = format!("{}{},{}", COORDINATE_PREFIX, lat, lon);
+    }
 
     loop {
         terminal.draw(|f| {
@@ -46,8 +109,18 @@ fn main() -> Result<(), Box<dyn Error>> {
             app.render(f, root_layout.as_slice()).unwrap();
         })?;
 
-        match read()? {
-            Event::Key(e) => match e.code {
+        if let Some(receiver) = &realtime {
+            while let Ok(feed) = receiver.try_recv() {
+                app.update_realtime(feed);
+            }
+        }
+
+        if !poll(TICK)? {
+            continue;
+        }
+
+        if let Event::Key(e) = read()? {
+            match e.code {
                 //These events should override block-specific ones
                 KeyCode::Char('q') => {
                     disable_raw_mode()?;
@@ -58,8 +131,7 @@ fn main() -> Result<(), Box<dyn Error>> {
                     app.block_focused = None;
                 },
                 _ => app.key_handler().handle_key(&e)
-            },
-            _ => {},
+            }
         }
     }
     Ok(())
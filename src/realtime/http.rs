@@ -0,0 +1,34 @@
+use std::error::Error;
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+// Minimal HTTP/1.0 GET, enough for feed endpoints and local stand-ins
+pub fn get(url: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+    let rest = url.strip_prefix("http://")
+        .ok_or_else(|| format!("Unsupported URL '{}', only http:// is supported", url))?;
+    let (host, path) = match rest.find('/') {
+        Some(i) => (&rest[..i], &rest[i..]),
+        None => (rest, "/"),
+    };
+    let address = if host.contains(':') { host.to_string() } else { format!("{}:80", host) };
+
+    let mut stream = TcpStream::connect(address)?;
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    write!(stream, "GET {} HTTP/1.0\r\nHost: {}\r\nConnection: close\r\n\r\n", path, host)?;
+
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response)?;
+
+    let header_end = response.windows(4).position(|w| w == b"\r\n\r\n")
+        .ok_or("Malformed HTTP response")?;
+    let status_line = String::from_utf8_lossy(&response[..header_end])
+        .lines().next().unwrap_or_default().to_string();
+    if status_line.split_whitespace().nth(1) != Some("200") {
+        return Err(format!("Request to {} failed: {}", url, status_line).into());
+    }
+
+    Ok(response.split_off(header_end + 4))
+}
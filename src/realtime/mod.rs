@@ -0,0 +1,463 @@
+use std::collections::HashMap;
+use std::convert::{TryFrom, TryInto};
+use std::error::Error;
+use std::fmt::Display;
+use std::fs;
+use std::sync::mpsc::{channel, Receiver};
+use std::thread;
+use std::time::Duration as StdDuration;
+
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveDateTime, TimeZone};
+use prost::Message;
+
+use crate::db::types::Stop;
+use crate::realtime::proto::{
+    Alert, EntitySelector, FeedMessage, STOP_NO_DATA, STOP_SKIPPED, StopTimeEvent, StopTimeUpdate,
+    TimeRange, TRIP_CANCELED, TripUpdate, VehiclePosition,
+};
+
+pub mod proto;
+pub mod record;
+mod http;
+
+const START_DATE_FORMAT: &str = "%Y%m%d";
+const PERIOD_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+//region Source
+pub enum RealtimeSource {
+    FILE(String),
+    HTTP(String),
+}
+
+impl RealtimeSource {
+    pub fn from(location: &str) -> RealtimeSource {
+        if location.starts_with("http://") || location.starts_with("https://") {
+            RealtimeSource::HTTP(location.to_string())
+        } else {
+            RealtimeSource::FILE(location.to_string())
+        }
+    }
+
+    pub fn fetch(&self) -> Result<Vec<u8>, Box<dyn Error>> {
+        Ok(match self {
+            RealtimeSource::FILE(path) => fs::read(path)?,
+            RealtimeSource::HTTP(url) => http::get(url)?,
+        })
+    }
+}
+
+// Fetches the source periodically on a background thread, optionally recording every snapshot
+pub fn spawn_poller(
+    source: RealtimeSource, interval: StdDuration, record_dir: Option<String>,
+) -> Receiver<Result<FeedMessage, String>> {
+    let (sender, receiver) = channel();
+
+    thread::spawn(move || loop {
+        let fetched = source.fetch()
+            .and_then(|bytes| Ok((FeedMessage::decode(bytes.as_slice())?, bytes)));
+        let (feed, record_error) = match fetched {
+            Ok((feed, bytes)) => {
+                // A failed recording is reported after the feed, which is used anyway
+                let record_error = record_dir.as_ref()
+                    .and_then(|dir| record::write_snapshot(dir, &bytes, feed.header.timestamp).err())
+                    .map(|e| format!("Recording failed: {}", e));
+                (Ok(feed), record_error)
+            }
+            Err(e) => (Err(e.to_string()), None),
+        };
+        if sender.send(feed).is_err()
+            || record_error.is_some_and(|e| sender.send(Err(e)).is_err()) {
+            // Receiver dropped, the UI has quit
+            break;
+        }
+        thread::sleep(interval);
+    });
+
+    receiver
+}
+//endregion
+
+//region Prediction
+#[derive(Clone, Copy, Default)]
+pub struct Prediction {
+    // Seconds, positive if late
+    pub arrival_delay: Option<i64>,
+    pub departure_delay: Option<i64>,
+    pub cancelled: bool,
+    pub skipped: bool,
+}
+
+// Latest TripUpdates and VehiclePositions of the feed, keyed by trip_id, and ServiceAlerts
+#[derive(Default)]
+pub struct Realtime {
+    pub timestamp: Option<DateTime<Local>>,
+    trip_updates: HashMap<u32, Vec<TripUpdate>>,
+    vehicles: HashMap<u32, VehiclePosition>,
+    alerts: Vec<Alert>,
+}
+
+impl Realtime {
+    // Feeds are full datasets, every update replaces the previous state.
+    // Events giving only a time are compared against the scheduled stops of their trip.
+    // A feed with timestamps out of range is an error, the previous state is kept
+    pub fn update<F>(&mut self, feed: FeedMessage, scheduled_stops: F) -> Result<(), String>
+    where
+        F: Fn(u32) -> Vec<Stop>,
+    {
+        let mut next = Realtime {
+            timestamp: feed.header.timestamp.map(local_time).transpose()?,
+            ..Realtime::default()
+        };
+
+        for entity in feed.entity.into_iter().filter(|e| e.is_deleted != Some(true)) {
+            if let Some(alert) = entity.alert {
+                for period in alert.active_period.iter() {
+                    period.start.map(local_time).transpose()?;
+                    period.end.map(local_time).transpose()?;
+                }
+                next.alerts.push(alert);
+            }
+            if let Some(vehicle) = entity.vehicle {
+                let trip_id = vehicle.trip.as_ref()
+                    .and_then(|t| t.trip_id.as_ref())
+                    .and_then(|id| id.parse::<u32>().ok());
+                if let (Some(id), true) = (trip_id, vehicle.position.is_some()) {
+                    next.vehicles.insert(id, vehicle);
+                }
+            }
+            if let Some(mut trip_update) = entity.trip_update {
+                let trip_id = trip_update.trip.trip_id.as_ref()
+                    .and_then(|id| id.parse::<u32>().ok());
+                if let Some(id) = trip_id {
+                    if has_time_only_event(&trip_update) {
+                        resolve_time_events(&mut trip_update, &scheduled_stops(id))?;
+                    }
+                    next.trip_updates.entry(id).or_default().push(trip_update);
+                }
+            }
+        }
+
+        *self = next;
+        Ok(())
+    }
+
+    pub fn predict(&self, stop: &Stop, service_date: NaiveDate) -> Prediction {
+        let trip_update = match self.find_trip_update(stop.trip_id, service_date) {
+            Some(x) => x,
+            None => return Prediction::default(),
+        };
+
+        if trip_update.trip.schedule_relationship == Some(TRIP_CANCELED) {
+            return Prediction { cancelled: true, ..Prediction::default() };
+        }
+
+        let exact = trip_update.stop_time_update.iter().find(|u| is_update_for(u, stop));
+        if let Some(update) = exact {
+            return match update.schedule_relationship {
+                Some(STOP_SKIPPED) => Prediction { skipped: true, ..Prediction::default() },
+                Some(STOP_NO_DATA) => Prediction::default(),
+                _ => Prediction {
+                    arrival_delay: event_delay(&update.arrival).or_else(|| event_delay(&update.departure)),
+                    departure_delay: event_delay(&update.departure).or_else(|| event_delay(&update.arrival)),
+                    ..Prediction::default()
+                },
+            };
+        }
+
+        // Delays propagate downstream until the next update
+        let previous = trip_update.stop_time_update.iter()
+            .filter(|u| u.stop_sequence.is_some_and(|seq| seq < stop.stop_sequence))
+            .max_by_key(|u| u.stop_sequence);
+        let delay = match previous {
+            Some(update) if update.schedule_relationship == Some(STOP_NO_DATA) => None,
+            Some(update) => event_delay(&update.departure)
+                .or_else(|| event_delay(&update.arrival))
+                .or_else(|| trip_update.delay.map(i64::from)),
+            None => trip_update.delay.map(i64::from),
+        };
+
+        Prediction {
+            arrival_delay: delay,
+            departure_delay: delay,
+            ..Prediction::default()
+        }
+    }
+
+    fn find_trip_update(&self, trip_id: u32, service_date: NaiveDate) -> Option<&TripUpdate> {
+        let start_date = service_date.format(START_DATE_FORMAT).to_string();
+
+        self.trip_updates.get(&trip_id)?.iter()
+            .find(|u| u.trip.start_date.as_ref().is_none_or(|d| d == &start_date))
+    }
+}
+
+fn is_update_for(update: &StopTimeUpdate, stop: &Stop) -> bool {
+    match (update.stop_sequence, &update.stop_id) {
+        (Some(seq), _) => seq == stop.stop_sequence,
+        (None, Some(id)) => id == &stop.stop_id,
+        _ => false,
+    }
+}
+
+fn event_delay(event: &Option<StopTimeEvent>) -> Option<i64> {
+    event.as_ref()?.delay.map(i64::from)
+}
+
+fn has_time_only_event(trip_update: &TripUpdate) -> bool {
+    trip_update.stop_time_update.iter()
+        .flat_map(|u| u.arrival.iter().chain(u.departure.iter()))
+        .any(|e| e.delay.is_none() && e.time.is_some())
+}
+
+// Sets the delay of events giving only a time, so it propagates like a given delay
+fn resolve_time_events(trip_update: &mut TripUpdate, stops: &[Stop]) -> Result<(), String> {
+    let start_date = trip_update.trip.start_date.as_ref()
+        .and_then(|d| NaiveDate::parse_from_str(d, START_DATE_FORMAT).ok());
+
+    for update in trip_update.stop_time_update.iter_mut() {
+        if let Some(stop) = stops.iter().find(|s| is_update_for(update, s)) {
+            resolve_time_event(&mut update.arrival, stop.arrival_time.or(stop.departure_time), start_date)?;
+            resolve_time_event(&mut update.departure, stop.departure_time.or(stop.arrival_time), start_date)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn resolve_time_event(
+    event: &mut Option<StopTimeEvent>, scheduled: Option<Duration>, start_date: Option<NaiveDate>,
+) -> Result<(), String> {
+    let event = match event {
+        Some(e) if e.delay.is_none() => e,
+        _ => return Ok(()),
+    };
+    let (time, scheduled) = match (event.time, scheduled) {
+        (Some(t), Some(s)) => (t, s),
+        _ => return Ok(()),
+    };
+    let event_time = local_time(time)?;
+    // Without a start date the trip is taken to run on the day of the event
+    let service_date = start_date.unwrap_or_else(|| {
+        event_time.date().naive_local() - Duration::days(scheduled.num_days())
+    });
+
+    let scheduled = Local.from_local_datetime(&(service_date.and_hms(0, 0, 0) + scheduled)).earliest();
+    event.delay = scheduled.and_then(|s| i32::try_from(time - s.timestamp()).ok());
+    Ok(())
+}
+
+// Local time of a feed timestamp, values out of range are feed errors
+fn local_time<T: Copy + Display + TryInto<i64>>(timestamp: T) -> Result<DateTime<Local>, String> {
+    timestamp.try_into().ok()
+        .and_then(|t| Local.timestamp_opt(t, 0).single())
+        .ok_or_else(|| format!("timestamp {} out of range", timestamp))
+}
+//endregion
+
+//region Vehicles
+#[derive(Clone)]
+pub struct Vehicle {
+    pub trip_id: u32,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub label: String,
+}
+
+impl Realtime {
+    // Vehicles with a position and a trip of the static feed
+    pub fn vehicles(&self) -> Vec<Vehicle> {
+        self.vehicles.iter()
+            .filter_map(|(trip_id, v)| {
+                let position = v.position.as_ref()?;
+                let label = v.vehicle.as_ref()
+                    .and_then(|d| d.label.as_ref().or(d.id.as_ref()))
+                    .map_or(trip_id.to_string(), |l| l.to_string());
+
+                Some(Vehicle {
+                    trip_id: *trip_id,
+                    latitude: f64::from(position.latitude),
+                    longitude: f64::from(position.longitude),
+                    label,
+                })
+            })
+            .collect()
+    }
+}
+//endregion
+
+//region Alerts
+// What a board row or station refers to, unset fields never match a selector
+#[derive(Default)]
+pub struct AlertScope<'a> {
+    pub stop_id: Option<&'a str>,
+    pub route_id: Option<&'a str>,
+    pub trip_id: Option<u32>,
+    pub agency_id: Option<&'a str>,
+    pub route_type: Option<i32>,
+}
+
+#[derive(Clone)]
+pub struct AlertInfo {
+    pub header: String,
+    pub description: String,
+    pub active_period: String,
+}
+
+impl Realtime {
+    // Alerts affecting the scope and active at the given time
+    pub fn alerts(&self, scope: &AlertScope, date_time: NaiveDateTime) -> Vec<AlertInfo> {
+        let timestamp = match Local.from_local_datetime(&date_time).earliest() {
+            Some(dt) => dt.timestamp(),
+            None => return Vec::new(),
+        };
+
+        self.alerts.iter()
+            .filter(|a| a.active_period.is_empty() || a.active_period.iter().any(|p| p.contains(timestamp)))
+            .filter(|a| a.informed_entity.iter().any(|e| selects(e, scope)))
+            .map(|a| AlertInfo {
+                header: a.header_text.as_ref().map_or(String::new(), |t| t.text()),
+                description: a.description_text.as_ref().map_or(String::new(), |t| t.text()),
+                active_period: format_periods(&a.active_period),
+            })
+            .collect()
+    }
+}
+
+// Every field set on the selector has to match the scope, selectors without any field match nothing
+fn selects(selector: &EntitySelector, scope: &AlertScope) -> bool {
+    let trip = selector.trip.as_ref();
+    let route_id = selector.route_id.as_ref()
+        .or_else(|| trip.and_then(|t| t.route_id.as_ref()));
+    let trip_id = trip.and_then(|t| t.trip_id.as_ref());
+
+    if route_id.is_none() && trip_id.is_none() && selector.stop_id.is_none()
+        && selector.agency_id.is_none() && selector.route_type.is_none() {
+        return false;
+    }
+
+    // Agency-wide and mode-wide alerts carry only the agency or route type
+    selector.agency_id.as_ref().is_none_or(|id| scope.agency_id == Some(id.as_str()))
+        && selector.route_type.is_none_or(|t| scope.route_type == Some(t))
+        && route_id.is_none_or(|id| scope.route_id == Some(id.as_str()))
+        && trip_id.is_none_or(|id| scope.trip_id.is_some_and(|t| id.parse() == Ok(t)))
+        && selector.stop_id.as_ref()
+            .is_none_or(|id| scope.stop_id.is_some_and(|s| same_station(s, id)))
+}
+
+// Platforms like "<stop_id>:1" belong to the station "<stop_id>"
+fn same_station(a: &str, b: &str) -> bool {
+    let (short, long) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+    long.strip_prefix(short).is_some_and(|rest| rest.is_empty() || rest.starts_with(':'))
+}
+
+fn format_periods(periods: &[TimeRange]) -> String {
+    if periods.is_empty() {
+        return String::from("Until further notice");
+    }
+
+    // Checked when the feed was read
+    let format = |t: u64| local_time(t).map_or(String::new(), |dt| dt.format(PERIOD_FORMAT).to_string());
+    periods.iter()
+        .map(|p| match (p.start, p.end) {
+            (Some(start), Some(end)) => format!("{} - {}", format(start), format(end)),
+            (Some(start), None) => format!("From {}", format(start)),
+            (None, Some(end)) => format!("Until {}", format(end)),
+            (None, None) => String::from("Until further notice"),
+        })
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+//endregion
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::realtime::proto::{FeedEntity, FeedHeader, TripDescriptor};
+
+    fn stop(stop_sequence: u32, departure: i64) -> Stop {
+        Stop {
+            arrival_time: Some(Duration::seconds(departure)),
+            departure_time: Some(Duration::seconds(departure)),
+            trip_id: 100,
+            stop_id: format!("S{}", stop_sequence),
+            stop_sequence,
+            stop_name: String::new(),
+            short_name: String::new(),
+            service_id: 1,
+            head_sign: String::new(),
+            route_id: String::new(),
+            agency_id: None,
+            route_type: None,
+        }
+    }
+
+    #[test]
+    fn time_only_events_propagate_as_delay() {
+        let service_date = NaiveDate::from_ymd(2026, 10, 19);
+        let stops = [stop(1, 8 * 3600), stop(2, 9 * 3600)];
+        // Leaves the first stop at 08:03:30 instead of 08:00
+        let time = Local.from_local_datetime(&service_date.and_hms(8, 3, 30)).unwrap().timestamp();
+        let trip_update = TripUpdate {
+            trip: TripDescriptor {
+                trip_id: Some(String::from("100")),
+                start_date: Some(String::from("20261019")),
+                ..TripDescriptor::default()
+            },
+            stop_time_update: vec![StopTimeUpdate {
+                stop_sequence: Some(1),
+                departure: Some(StopTimeEvent { time: Some(time), ..StopTimeEvent::default() }),
+                ..StopTimeUpdate::default()
+            }],
+            ..TripUpdate::default()
+        };
+        let feed = FeedMessage {
+            entity: vec![FeedEntity { trip_update: Some(trip_update), ..FeedEntity::default() }],
+            ..FeedMessage::default()
+        };
+
+        let mut realtime = Realtime::default();
+        realtime.update(feed, |_| vec![stop(1, 8 * 3600), stop(2, 9 * 3600)]).unwrap();
+
+        assert_eq!(realtime.predict(&stops[0], service_date).departure_delay, Some(210));
+        assert_eq!(realtime.predict(&stops[1], service_date).arrival_delay, Some(210));
+    }
+
+    #[test]
+    fn out_of_range_timestamps_fail_the_feed() {
+        let mut realtime = Realtime::default();
+        let valid = FeedMessage {
+            header: FeedHeader { timestamp: Some(1_760_000_000), ..FeedHeader::default() },
+            ..FeedMessage::default()
+        };
+        realtime.update(valid, |_| Vec::new()).unwrap();
+
+        let invalid = FeedMessage {
+            header: FeedHeader { timestamp: Some(u64::MAX), ..FeedHeader::default() },
+            ..FeedMessage::default()
+        };
+        assert!(realtime.update(invalid, |_| Vec::new()).is_err());
+        assert_eq!(realtime.timestamp.map(|t| t.timestamp()), Some(1_760_000_000));
+    }
+
+    #[test]
+    fn selectors_match_scope() {
+        let scope = AlertScope {
+            stop_id: Some("A:1"),
+            route_id: Some("R1"),
+            trip_id: Some(100),
+            agency_id: Some("1"),
+            route_type: Some(2),
+        };
+        let agency = |id: &str| EntitySelector { agency_id: Some(id.to_string()), ..EntitySelector::default() };
+        let mode = |t: i32| EntitySelector { route_type: Some(t), ..EntitySelector::default() };
+
+        assert!(selects(&agency("1"), &scope));
+        assert!(!selects(&agency("2"), &scope));
+        assert!(selects(&mode(2), &scope));
+        assert!(!selects(&mode(3), &scope));
+        assert!(selects(&EntitySelector { stop_id: Some(String::from("A")), ..mode(2) }, &scope));
+        assert!(!selects(&EntitySelector::default(), &scope));
+        // Station alerts have no agency or route type
+        assert!(!selects(&agency("1"), &AlertScope { stop_id: Some("A"), ..AlertScope::default() }));
+    }
+}
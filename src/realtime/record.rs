@@ -0,0 +1,128 @@
+use std::error::Error;
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use prost::Message;
+
+use crate::realtime::proto::FeedMessage;
+
+const SNAPSHOT_EXTENSION: &str = "pb";
+
+// Snapshots are stored as "<unix timestamp>.pb", the raw protobuf as received.
+// Further snapshots with the same timestamp get a sequence number, "<unix timestamp>-1.pb"
+pub fn write_snapshot(
+    dir: &str, bytes: &[u8], timestamp: Option<u64>,
+) -> Result<(), Box<dyn Error>> {
+    let timestamp = match timestamp {
+        Some(t) => t,
+        None => SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+    };
+
+    fs::create_dir_all(dir)?;
+    let mut sequence = 0;
+    loop {
+        let name = match sequence {
+            0 => format!("{}.{}", timestamp, SNAPSHOT_EXTENSION),
+            _ => format!("{}-{}.{}", timestamp, sequence, SNAPSHOT_EXTENSION),
+        };
+        // create_new fails if the file exists, so no snapshot is overwritten
+        match OpenOptions::new().write(true).create_new(true).open(Path::new(dir).join(name)) {
+            Ok(mut file) => return Ok(file.write_all(bytes)?),
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => sequence += 1,
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+// Recorded snapshots of the directory, ordered by timestamp
+pub fn list_snapshots(dir: &str) -> Result<Vec<(u64, PathBuf)>, Box<dyn Error>> {
+    let mut snapshots = Vec::new();
+
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().and_then(|e| e.to_str()) != Some(SNAPSHOT_EXTENSION) {
+            continue;
+        }
+        if let Some((timestamp, sequence)) = path.file_stem().and_then(|s| s.to_str()).and_then(parse_stem) {
+            snapshots.push((timestamp, sequence, path));
+        }
+    }
+    snapshots.sort();
+
+    Ok(snapshots.into_iter().map(|(timestamp, _, path)| (timestamp, path)).collect())
+}
+
+// Timestamp and sequence number of "<unix timestamp>[-<sequence>]"
+fn parse_stem(stem: &str) -> Option<(u64, u32)> {
+    match stem.split_once('-') {
+        Some((timestamp, sequence)) => Some((timestamp.parse().ok()?, sequence.parse().ok()?)),
+        None => Some((stem.parse().ok()?, 0)),
+    }
+}
+
+// Sends the recorded snapshots in order, waiting the recorded time between them divided by speed
+pub fn spawn_replay(
+    snapshots: Vec<(u64, PathBuf)>, speed: f64,
+) -> Receiver<Result<FeedMessage, String>> {
+    let (sender, receiver) = channel();
+
+    thread::spawn(move || {
+        let mut previous: Option<u64> = None;
+
+        for (timestamp, path) in snapshots {
+            if let Some(p) = previous {
+                thread::sleep(Duration::from_secs_f64(timestamp.saturating_sub(p) as f64 / speed));
+            }
+            previous = Some(timestamp);
+
+            let feed = fs::read(&path)
+                .map_err(|e| format!("{}: {}", path.display(), e))
+                .and_then(|bytes| FeedMessage::decode(bytes.as_slice())
+                    .map_err(|e| format!("{}: {}", path.display(), e)))
+                .map(|mut feed| {
+                    // The file name is the replay clock
+                    feed.header.timestamp = Some(timestamp);
+                    feed
+                });
+
+            if sender.send(feed).is_err() {
+                // Receiver dropped, the UI has quit
+                break;
+            }
+        }
+    });
+
+    receiver
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snapshots_with_same_timestamp_are_kept_in_order() {
+        let dir = std::env::temp_dir().join(format!("gtfs-viewer-record-{}", std::process::id()));
+        let dir = dir.to_str().unwrap();
+
+        for bytes in [b"a", b"b", b"c"].iter() {
+            write_snapshot(dir, *bytes, Some(1000)).unwrap();
+        }
+        write_snapshot(dir, b"d", Some(999)).unwrap();
+
+        let contents: Vec<(u64, Vec<u8>)> = list_snapshots(dir).unwrap().into_iter()
+            .map(|(timestamp, path)| (timestamp, fs::read(path).unwrap()))
+            .collect();
+        fs::remove_dir_all(dir).unwrap();
+
+        assert_eq!(contents, vec![
+            (999, b"d".to_vec()),
+            (1000, b"a".to_vec()),
+            (1000, b"b".to_vec()),
+            (1000, b"c".to_vec()),
+        ]);
+    }
+}
@@ -0,0 +1,355 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::Duration;
+
+use chrono::{Local, NaiveDate};
+use serde::Serialize;
+
+use crate::cli::{board, stations, trip};
+use crate::config::{BoardArgs, OutputFormat, parse_date, parse_date_time, ServeArgs, StationsArgs, TripArgs};
+use crate::db::GTFSDatabase;
+use crate::db::types::BoardType;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+// Requests are handled one after another, the database connection is not shared between threads
+pub fn serve(db: &GTFSDatabase, args: &ServeArgs) -> Result<(), Box<dyn Error>> {
+    let listener = TcpListener::bind((args.host.as_str(), args.port))?;
+    eprintln!("Serving on http://{}", listener.local_addr()?);
+
+    for stream in listener.incoming() {
+        let result = stream.map_err(|e| e.into()).and_then(|mut s| handle(db, &mut s));
+        if let Err(e) = result {
+            eprintln!("Request failed: {}", e);
+        }
+    }
+
+    Ok(())
+}
+
+//region Request
+enum Status {
+    OK,
+    INVALID,
+    MISSING,
+    UNSUPPORTED,
+    FAILED,
+}
+
+impl Status {
+    fn line(&self) -> &'static str {
+        match self {
+            Status::OK => "200 OK",
+            Status::INVALID => "400 Bad Request",
+            Status::MISSING => "404 Not Found",
+            Status::UNSUPPORTED => "405 Method Not Allowed",
+            Status::FAILED => "500 Internal Server Error",
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ErrorBody {
+    error: String,
+}
+
+fn handle(db: &GTFSDatabase, stream: &mut TcpStream) -> Result<(), Box<dyn Error>> {
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    let mut reader = BufReader::new(&*stream);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // Headers are not needed, only consumed
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 || line.trim_end().is_empty() {
+            break;
+        }
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let (status, body) = match (parts.next(), parts.next()) {
+        (Some("GET"), Some(target)) => {
+            let (path, query) = target.split_once('?').unwrap_or((target, ""));
+            match parse_query(query) {
+                Some(params) => route(db, path, &params),
+                None => (Status::INVALID, error_body("Malformed percent-encoding in query")),
+            }
+        }
+        (Some(_), Some(_)) => (Status::UNSUPPORTED, error_body("Only GET is supported")),
+        _ => (Status::INVALID, error_body("Malformed request")),
+    };
+
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: application/json; charset=utf-8\r\nContent-Length: {}\r\n\
+        Access-Control-Allow-Origin: *\r\nConnection: close\r\n\r\n{}",
+        status.line(), body.len(), body,
+    )?;
+
+    Ok(())
+}
+
+// None if a key or value is not validly percent-encoded
+fn parse_query(query: &str) -> Option<HashMap<String, String>> {
+    query.split('&')
+        .filter(|p| !p.is_empty())
+        .map(|p| {
+            let (key, value) = p.split_once('=').unwrap_or((p, ""));
+            Some((percent_decode(key)?, percent_decode(value)?))
+        })
+        .collect()
+}
+
+// None for an incomplete escape, non-hex digits or bytes which are not UTF-8
+fn percent_decode(value: &str) -> Option<String> {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' => {
+                let hex = bytes.get(i + 1..i + 3).filter(|h| h.iter().all(u8::is_ascii_hexdigit))?;
+                decoded.push(u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok()?);
+                i += 2;
+            }
+            b => decoded.push(b),
+        }
+        i += 1;
+    }
+
+    String::from_utf8(decoded).ok()
+}
+
+fn error_body(message: &str) -> String {
+    serde_json::to_string(&ErrorBody { error: message.to_string() }).unwrap_or_default()
+}
+//endregion
+
+//region Routes
+fn route(db: &GTFSDatabase, path: &str, params: &HashMap<String, String>) -> (Status, String) {
+    let segments: Vec<String> = match path.split('/').filter(|s| !s.is_empty()).map(percent_decode).collect() {
+        Some(segments) => segments,
+        None => return (Status::INVALID, error_body(&format!("Malformed percent-encoding in path '{}'", path))),
+    };
+
+    let result = match segments.iter().map(|s| s.as_str()).collect::<Vec<&str>>().as_slice() {
+        ["stations"] => stations_json(db, params),
+        ["board"] => board_json(db, params),
+        ["trips", trip] => trip_json(db, trip, params),
+        ["services", service_id] => service_json(db, service_id),
+        _ => return (Status::MISSING, error_body(&format!("Unknown path '{}'", path))),
+    };
+
+    match result {
+        Ok(body) => (Status::OK, body),
+        Err(ApiError::Invalid(e)) => (Status::INVALID, error_body(&e.to_string())),
+        // Lookups fail with plain messages, everything else is a database error
+        Err(ApiError::Lookup(e)) if e.is::<rusqlite::Error>() => {
+            (Status::FAILED, error_body(&e.to_string()))
+        }
+        Err(ApiError::Lookup(e)) => (Status::MISSING, error_body(&e.to_string())),
+    }
+}
+
+enum ApiError {
+    // Missing or malformed parameters
+    Invalid(Box<dyn Error>),
+    Lookup(Box<dyn Error>),
+}
+
+fn to_json<T>(value: &T) -> Result<String, ApiError>
+    where T: Serialize + ?Sized
+{
+    serde_json::to_string(value).map_err(|e| ApiError::Lookup(e.into()))
+}
+
+fn required<'a>(params: &'a HashMap<String, String>, name: &str) -> Result<&'a str, ApiError> {
+    params.get(name).map(|v| v.as_str())
+        .ok_or_else(|| ApiError::Invalid(format!("Missing parameter '{}'", name).into()))
+}
+
+fn date_param(params: &HashMap<String, String>) -> Result<NaiveDate, ApiError> {
+    match params.get("date") {
+        Some(value) => parse_date(value).map_err(ApiError::Invalid),
+        None => Ok(Local::today().naive_local()),
+    }
+}
+
+// GET /stations?q=<query>
+fn stations_json(db: &GTFSDatabase, params: &HashMap<String, String>) -> Result<String, ApiError> {
+    let args = StationsArgs {
+        query: required(params, "q")?.to_string(),
+        format: OutputFormat::JSON,
+    };
+
+    to_json(&stations::fetch_rows(db, &args).map_err(ApiError::Lookup)?)
+}
+
+// GET /board?station=<name>&at=<YYYY-MM-DD HH:MM>&arrivals=true&limit=<n>
+fn board_json(db: &GTFSDatabase, params: &HashMap<String, String>) -> Result<String, ApiError> {
+    let args = BoardArgs {
+        station: required(params, "station")?.to_string(),
+        date_time: match params.get("at") {
+            Some(value) => parse_date_time(value).map_err(ApiError::Invalid)?,
+            None => Local::now().naive_local(),
+        },
+        board_type: match params.get("arrivals").map(|v| v.as_str()) {
+            Some("true") | Some("1") | Some("") => BoardType::ARRIVAL,
+            _ => BoardType::DEPARTURE,
+        },
+        limit: match params.get("limit") {
+            Some(value) => Some(value.parse().map_err(
+                |_| ApiError::Invalid(format!("Invalid limit '{}', expected a number", value).into())
+            )?),
+            None => None,
+        },
+        format: OutputFormat::JSON,
+    };
+
+    to_json(&board::fetch_rows(db, &args).map_err(ApiError::Lookup)?)
+}
+
+// GET /trips/<number>?date=<YYYY-MM-DD>&id=true, with id the trip_id is given instead
+fn trip_json(
+    db: &GTFSDatabase, trip: &str, params: &HashMap<String, String>,
+) -> Result<String, ApiError> {
+    let args = TripArgs {
+        trip: trip.to_string(),
+        by_id: matches!(params.get("id").map(|v| v.as_str()), Some("true") | Some("1") | Some("")),
+        date: date_param(params)?,
+        format: OutputFormat::JSON,
+    };
+
+    to_json(&trip::fetch_rows(db, &args).map_err(ApiError::Lookup)?)
+}
+
+#[derive(Serialize)]
+struct ServiceInfo {
+    service_id: u16,
+    start_date: String,
+    end_date: String,
+    weekdays: Vec<&'static str>,
+    added_dates: Vec<String>,
+    removed_dates: Vec<String>,
+    operating_days: String,
+}
+
+// GET /services/<service_id>
+fn service_json(db: &GTFSDatabase, service_id: &str) -> Result<String, ApiError> {
+    let service_id: u16 = service_id.parse().map_err(
+        |_| ApiError::Invalid(format!("Invalid service id '{}'", service_id).into())
+    )?;
+    let service = db.get_service(service_id).ok_or_else(
+        || ApiError::Lookup(format!("No service {}", service_id).into())
+    )?;
+    let format_dates = |exception_type: u8| service.exception_dates(exception_type).iter()
+        .map(|d| d.format(DATE_FORMAT).to_string())
+        .collect();
+
+    to_json(&ServiceInfo {
+        service_id,
+        start_date: service.start_date.format(DATE_FORMAT).to_string(),
+        end_date: service.end_date.format(DATE_FORMAT).to_string(),
+        weekdays: service.operating_weekdays.short_names(),
+        added_dates: format_dates(1),
+        removed_dates: format_dates(2),
+        operating_days: service.operating_days(),
+    })
+}
+//endregion
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use rusqlite::Connection;
+    use serde_json::{json, Value};
+
+    use super::*;
+
+    const SCHEMA_SQL: &str = include_str!("../scripts/schema.sql");
+
+    const FIXTURE_SQL: &str = "\
+        INSERT INTO service VALUES (1, 1, 1, 1, 1, 1, 1, 1, '20260101', '20261231'); \
+        INSERT INTO agency (agency_id, name) VALUES (1, 'DB'); \
+        INSERT INTO route (route_id, agency_id, short_name, long_name, route_type) VALUES ('R1', 1, 'ICE', '', 101); \
+        INSERT INTO stop (stop_id, name, latitude, longitude) VALUES \
+            ('A', 'Berlin Hbf', 52.525, 13.369), ('B', 'Hamburg Hbf', 53.553, 10.007); \
+        INSERT INTO trip (route_id, service_id, trip_id, headsign, short_name) VALUES ('R1', '1', 100, 'Hamburg Hbf', '598'); \
+        INSERT INTO stop_time (trip_id, arrival_time, departure_time, stop_id, stop_sequence) VALUES \
+            (100, 28800, 29100, 'A', 1), (100, 35100, 35100, 'B', 2);";
+
+    // Writes the request, lets the server handle it and returns the status line and JSON body
+    fn request(db: &GTFSDatabase, listener: &TcpListener, target: &str) -> (String, Value) {
+        let mut client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        write!(client, "GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", target).unwrap();
+
+        let (mut stream, _) = listener.accept().unwrap();
+        handle(db, &mut stream).unwrap();
+        drop(stream);
+
+        let mut response = String::new();
+        client.read_to_string(&mut response).unwrap();
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        (head.lines().next().unwrap().to_string(), serde_json::from_str(body).unwrap())
+    }
+
+    #[test]
+    fn serves_json_with_status() {
+        let path = std::env::temp_dir().join(format!("gtfs-viewer-server-{}.db", std::process::id()));
+        let path = path.to_str().unwrap();
+        // Left over if a previous run failed
+        let _ = std::fs::remove_file(path);
+        let fixture = Connection::open(path).unwrap();
+        fixture.execute_batch(SCHEMA_SQL).unwrap();
+        fixture.execute_batch(FIXTURE_SQL).unwrap();
+        drop(fixture);
+
+        let db = GTFSDatabase::new(path, |_| {}).unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+
+        let (status, body) = request(&db, &listener, "/board?station=Berlin%20Hbf&at=2026-10-19+07:00");
+        assert_eq!(status, "HTTP/1.1 200 OK");
+        assert_eq!(body, json!([{
+            "trip_id": 100,
+            "number": "598",
+            "destination": "Hamburg Hbf",
+            "arrival": "08:00",
+            "departure": "08:05",
+            "service_date": "2026-10-19",
+            "stop_id": "A",
+        }]));
+
+        let (status, body) = request(&db, &listener, "/departures");
+        assert_eq!(status, "HTTP/1.1 404 Not Found");
+        assert_eq!(body, json!({ "error": "Unknown path '/departures'" }));
+
+        let (status, body) = request(&db, &listener, "/trips/%E2%28");
+        assert_eq!(status, "HTTP/1.1 400 Bad Request");
+        assert_eq!(body, json!({ "error": "Malformed percent-encoding in path '/trips/%E2%28'" }));
+
+        let (status, _) = request(&db, &listener, "/trips/%4");
+        assert_eq!(status, "HTTP/1.1 400 Bad Request");
+
+        // Numbers are train numbers, trip ids only with id
+        let (status, body) = request(&db, &listener, "/trips/598?date=2026-10-19");
+        assert_eq!(status, "HTTP/1.1 200 OK");
+        assert_eq!(body[0]["trip_id"], 100);
+
+        let (status, _) = request(&db, &listener, "/trips/100?date=2026-10-19");
+        assert_eq!(status, "HTTP/1.1 404 Not Found");
+
+        let (status, body) = request(&db, &listener, "/trips/100?date=2026-10-19&id=true");
+        assert_eq!(status, "HTTP/1.1 200 OK");
+        assert_eq!(body[1]["station"], "Hamburg Hbf");
+
+        drop(db);
+        std::fs::remove_file(path).unwrap();
+    }
+}
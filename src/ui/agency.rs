@@ -0,0 +1,83 @@
+use std::error::Error;
+
+use chrono::NaiveDate;
+use crossterm::event::KeyEvent;
+use tui::layout::Constraint;
+use tui::style::{Color, Modifier, Style};
+use tui::widgets::{Cell, Row, Table, TableState};
+
+use crate::db::types::AgencyStats;
+use crate::handler::{KeyHandler, scroll_nav};
+use crate::ui::{create_block, UIBlock, WidgetData};
+
+// Shown in place of the board, choosing an agency lists its routes
+pub struct AgencyPane {
+    pub data: WidgetData<AgencyStats, String, TableState>,
+    pub visible: bool,
+    // Date the statistics were fetched for
+    pub date: Option<NaiveDate>,
+}
+
+impl Default for AgencyPane {
+    fn default() -> Self {
+        Self {
+            data: WidgetData::new(String::new()),
+            visible: false,
+            date: None,
+        }
+    }
+}
+
+impl AgencyPane {
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    pub fn selected_name(&self) -> Option<String> {
+        self.data.get_selected_item().map(|a| a.name.to_string())
+    }
+}
+
+impl KeyHandler for AgencyPane {
+    fn handle_key(&mut self, event: &KeyEvent) {
+        scroll_nav(&mut self.data, &event.code);
+    }
+}
+
+impl<'a> UIBlock<Table<'a>> for AgencyPane {
+    fn build(&self, hovered: bool, selected: bool) -> Result<Table<'a>, Box<dyn Error>> {
+        let rows: Vec<Row> = self.data.items.iter()
+            .map(|a| Row::new(vec![
+                Cell::from(a.name.to_string()),
+                Cell::from(a.routes.to_string()),
+                Cell::from(a.trips.to_string()),
+                Cell::from(a.trips_on_date.to_string()),
+                Cell::from(a.stops.to_string()),
+                Cell::from(a.departures.to_string()),
+            ]))
+            .collect();
+
+        let title = match self.date {
+            Some(date) => format!("Agencies {}", date.format("%Y-%m-%d")),
+            None => String::from("Agencies"),
+        };
+
+        Ok(Table::new(rows)
+            .style(Style::default().fg(Color::White))
+            .header(
+                Row::new(vec!["Agency", "Routes", "Trips", "Running", "Stops", "Departures"])
+                    .style(Style::default().add_modifier(Modifier::BOLD))
+            )
+            .highlight_style(Style::default().fg(Color::Magenta))
+            .highlight_symbol(">>")
+            .block(create_block(hovered, selected).title(title))
+            .widths(&[
+                Constraint::Percentage(30),
+                Constraint::Percentage(12),
+                Constraint::Percentage(12),
+                Constraint::Percentage(12),
+                Constraint::Percentage(12),
+                Constraint::Percentage(15),
+            ]))
+    }
+}
@@ -0,0 +1,50 @@
+use tui::layout::Rect;
+use tui::style::{Color, Modifier, Style};
+use tui::text::{Span, Spans, Text};
+use tui::widgets::{Block, Borders, Paragraph, Wrap};
+
+use crate::realtime::AlertInfo;
+
+const POPUP_MARGIN: u16 = 2;
+
+pub fn alert_popup<'a>(alerts: &[AlertInfo]) -> Paragraph<'a> {
+    let mut lines: Vec<Spans> = Vec::new();
+
+    for alert in alerts {
+        if !lines.is_empty() {
+            lines.push(Spans::from(""));
+        }
+        lines.push(Spans::from(Span::styled(
+            alert.header.to_string(),
+            Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+        )));
+        lines.push(Spans::from(Span::styled(
+            alert.active_period.to_string(),
+            Style::default().fg(Color::DarkGray),
+        )));
+        lines.extend(alert.description.lines().map(|l| Spans::from(l.to_string())));
+    }
+
+    if lines.is_empty() {
+        lines.push(Spans::from("No alerts"));
+    }
+
+    Paragraph::new(Text::from(lines))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Yellow))
+                .title("Alerts")
+        )
+        .wrap(Wrap { trim: true })
+}
+
+// Area inside the given one, leaving a margin on each side
+pub fn popup_area(area: Rect) -> Rect {
+    Rect {
+        x: area.x + POPUP_MARGIN.min(area.width / 2),
+        y: area.y + POPUP_MARGIN.min(area.height / 2),
+        width: area.width.saturating_sub(2 * POPUP_MARGIN),
+        height: area.height.saturating_sub(2 * POPUP_MARGIN),
+    }
+}
@@ -1,58 +1,137 @@
 use std::error::Error;
 
+use chrono::{Local, NaiveDateTime};
 use tui::layout::Constraint;
 use tui::style::{Color, Modifier, Style};
-use tui::widgets::{Row, Table, TableState};
+use tui::widgets::{Cell, Row, Table, TableState};
 
-use crate::db::types::DisplayStop;
-use crate::ui::{create_block, UIBlock, WidgetData};
+use crate::db::types::{BoardType, DisplayStop, Stop};
+use crate::export::ExportFormat;
+use crate::realtime::{AlertScope, Realtime};
+use crate::ui::{alert_cell, create_block, delay_cell, UIBlock, WidgetData};
 use crate::handler::{KeyHandler, scroll_nav};
-use crossterm::event::KeyEvent;
+use crossterm::event::{KeyCode, KeyEvent};
 
 pub struct Board {
     pub data: WidgetData<DisplayStop, u32, TableState>,
+    // Scheduled stops and the date and time they were fetched for
+    pub stops: Vec<Stop>,
+    pub station: String,
+    pub board_type: BoardType,
+    pub date_time: NaiveDateTime,
+    pub realtime_status: String,
+    // Requested by key, written by the App which knows the export directory
+    pub export_request: Option<ExportFormat>,
+    pub export_status: String,
+    // Popup with the alerts of the selected row
+    pub show_alerts: bool,
 }
 
 impl Default for Board {
     fn default() -> Self {
         Self {
             data: WidgetData::new(0),
+            stops: Vec::new(),
+            station: String::new(),
+            board_type: BoardType::DEPARTURE,
+            date_time: Local::now().naive_local(),
+            realtime_status: String::new(),
+            export_request: None,
+            export_status: String::new(),
+            show_alerts: false,
         }
     }
 }
 
+impl Board {
+    pub fn display_stops(&self, realtime: &Realtime) -> Vec<DisplayStop> {
+        self.stops.iter()
+            .map(|s| {
+                let scope = AlertScope {
+                    stop_id: Some(&s.stop_id),
+                    route_id: Some(&s.route_id),
+                    trip_id: Some(s.trip_id),
+                    agency_id: s.agency_id.as_deref(),
+                    route_type: s.route_type,
+                };
+                let service_date = s.service_date(&self.board_type, &self.date_time);
+                DisplayStop {
+                    alerts: realtime.alerts(&scope, self.date_time),
+                    ..DisplayStop::from(
+                        s, &self.board_type, service_date, self.date_time, &realtime.predict(s, service_date),
+                    )
+                }
+            })
+            .collect()
+    }
+}
+
+impl Board {
+    fn title(&self) -> String {
+        [&self.realtime_status, &self.export_status].iter()
+            .filter(|s| !s.is_empty())
+            .map(|s| s.to_string())
+            .collect::<Vec<String>>()
+            .join(" | ")
+    }
+}
+
 impl KeyHandler for Board {
     fn handle_key(&mut self, event: &KeyEvent) {
-        scroll_nav(&mut self.data, &event.code);
+        match event.code {
+            KeyCode::Char('i') => self.show_alerts = !self.show_alerts,
+            KeyCode::Char('C') => self.export_request = Some(ExportFormat::CSV),
+            KeyCode::Char('J') => self.export_request = Some(ExportFormat::JSON),
+            KeyCode::Char('T') => self.export_request = Some(ExportFormat::TEXT),
+            _ => scroll_nav(&mut self.data, &event.code),
+        }
     }
 }
 
 impl<'a> UIBlock<Table<'a>> for Board {
     fn build(&self, hovered: bool, selected: bool) -> Result<Table<'a>, Box<dyn Error>> {
         let rows: Vec<Row> = self.data.items.iter()
-            .map(|s| Row::new(vec![
-                s.trip_id.to_string(),
-                s.short_name.to_string(),
-                s.head_sign.to_string(),
-                s.arr_time.to_string(),
-                s.dep_time.to_string(),
-            ]))
+            .map(|s| {
+                let row = Row::new(vec![
+                    alert_cell(&s.alerts),
+                    Cell::from(s.trip_id.to_string()),
+                    Cell::from(s.short_name.to_string()),
+                    Cell::from(s.head_sign.to_string()),
+                    Cell::from(s.arr_time.to_string()),
+                    Cell::from(s.dep_time.to_string()),
+                    Cell::from(s.expected_time.clone().unwrap_or_default()),
+                    if s.cancelled {
+                        Cell::from("cancelled").style(Style::default().fg(Color::Red))
+                    } else {
+                        delay_cell(s.delay(&self.board_type))
+                    },
+                ]);
+
+                if s.cancelled {
+                    row.style(Style::default().add_modifier(Modifier::CROSSED_OUT))
+                } else {
+                    row
+                }
+            })
             .collect();
 
         let table = Table::new(rows)
             .style(Style::default().fg(Color::White))
             .header(
-                Row::new(vec!["ID", "Nr.", "Destination", "Arr.", "Dep."])
+                Row::new(vec!["", "ID", "Nr.", "Destination", "Arr.", "Dep.", "Exp.", "Delay"])
                     .style(Style::default().add_modifier(Modifier::BOLD))
             )
             .highlight_style(Style::default().fg(Color::Magenta))
             .highlight_symbol(">>")
-            .block(create_block(hovered, selected))
+            .block(create_block(hovered, selected).title(self.title()))
             .widths(&[
-                Constraint::Percentage(20),
-                Constraint::Percentage(10),
-                Constraint::Percentage(40),
+                Constraint::Length(1),
                 Constraint::Percentage(15),
+                Constraint::Percentage(10),
+                Constraint::Percentage(28),
+                Constraint::Percentage(10),
+                Constraint::Percentage(10),
+                Constraint::Percentage(10),
                 Constraint::Percentage(15),
             ]);
 
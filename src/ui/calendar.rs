@@ -0,0 +1,143 @@
+use std::error::Error;
+
+use chrono::{Datelike, Duration, Local, NaiveDate, Weekday};
+use crossterm::event::{KeyCode, KeyEvent};
+use tui::style::{Color, Modifier, Style};
+use tui::text::{Span, Spans, Text};
+use tui::widgets::{Paragraph, Wrap};
+
+use crate::db::types::{Service, WEEKDAY_NAMES};
+use crate::handler::KeyHandler;
+use crate::ui::{create_block, UIBlock};
+
+const DATE_FORMAT: &str = "%d %b %Y";
+
+pub struct ServiceCalendar {
+    pub service: Option<Service>,
+    // First day of the displayed month
+    pub month: NaiveDate,
+    // Date selected in the menu, marked in the grid
+    pub marked_date: NaiveDate,
+}
+
+impl Default for ServiceCalendar {
+    fn default() -> Self {
+        let today = Local::today().naive_local();
+        Self {
+            service: None,
+            month: first_of_month(&today),
+            marked_date: today,
+        }
+    }
+}
+
+impl ServiceCalendar {
+    pub fn set_service(&mut self, service: Option<Service>, date: NaiveDate) {
+        self.service = service;
+        self.month = first_of_month(&date);
+        self.marked_date = date;
+    }
+
+    fn build_summary(&self, service: &Service) -> Vec<Spans<'static>> {
+        let mut lines = vec![
+            Spans::from(format!(
+                "Valid:   {} – {}",
+                service.start_date.format(DATE_FORMAT),
+                service.end_date.format(DATE_FORMAT),
+            )),
+            Spans::from(format!(
+                "Days:    {}",
+                service.operating_weekdays.short_names().join(" "),
+            )),
+        ];
+
+        for (label, exception_type) in [("Added:  ", 1), ("Removed:", 2)].iter() {
+            let dates: Vec<String> = service.exception_dates(*exception_type).iter()
+                .map(|d| d.format(DATE_FORMAT).to_string())
+                .collect();
+            if !dates.is_empty() {
+                lines.push(Spans::from(format!("{} {}", label, dates.join(", "))));
+            }
+        }
+
+        lines
+    }
+
+    fn build_grid(&self, service: &Service) -> Vec<Spans<'static>> {
+        let mut lines = vec![
+            Spans::from(Span::styled(
+                self.month.format("%B %Y").to_string(),
+                Style::default().add_modifier(Modifier::BOLD),
+            )),
+            Spans::from(WEEKDAY_NAMES.iter()
+                .map(|n| format!("{:>4}", &n[..2]))
+                .collect::<String>()),
+        ];
+
+        // Leading blanks up to the weekday of the first day
+        let offset = self.month.weekday().num_days_from_monday() as usize;
+        let mut week: Vec<Span> = vec![Span::raw("    "); offset];
+
+        let mut date = self.month;
+        while date.month() == self.month.month() {
+            let mut style = if service.is_available(&date) {
+                Style::default().fg(Color::Green).add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(Color::DarkGray)
+            };
+            if date == self.marked_date {
+                style = style.add_modifier(Modifier::REVERSED);
+            }
+
+            week.push(Span::raw("  "));
+            week.push(Span::styled(format!("{:>2}", date.day()), style));
+
+            // Wrap after Sunday, counting days rather than spans
+            if date.weekday() == Weekday::Sun {
+                lines.push(Spans::from(week));
+                week = Vec::new();
+            }
+            date += Duration::days(1);
+        }
+
+        if !week.is_empty() {
+            lines.push(Spans::from(week));
+        }
+
+        lines
+    }
+}
+
+impl KeyHandler for ServiceCalendar {
+    fn handle_key(&mut self, event: &KeyEvent) {
+        self.month = match event.code {
+            KeyCode::Left | KeyCode::Char('h') => first_of_month(&(self.month - Duration::days(1))),
+            KeyCode::Right | KeyCode::Char('l') => first_of_month(&(self.month + Duration::days(31))),
+            KeyCode::Char('t') => first_of_month(&self.marked_date),
+            _ => self.month,
+        }
+    }
+}
+
+impl<'a> UIBlock<Paragraph<'a>> for ServiceCalendar {
+    fn build(&self, hovered: bool, selected: bool) -> Result<Paragraph<'a>, Box<dyn Error>> {
+        let lines = match &self.service {
+            Some(service) => {
+                let mut lines = self.build_summary(service);
+                lines.push(Spans::from(""));
+                lines.extend(self.build_grid(service));
+                lines
+            }
+            None => vec![Spans::from("No trip selected")],
+        };
+
+        Ok(Paragraph::new(Text::from(lines))
+            .block(create_block(hovered, selected).title("Service calendar"))
+            .wrap(Wrap { trim: false })
+        )
+    }
+}
+
+fn first_of_month(date: &NaiveDate) -> NaiveDate {
+    NaiveDate::from_ymd(date.year(), date.month(), 1)
+}
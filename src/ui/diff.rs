@@ -0,0 +1,81 @@
+use std::error::Error;
+
+use crossterm::event::KeyEvent;
+use tui::layout::Constraint;
+use tui::style::{Color, Modifier, Style};
+use tui::widgets::{Cell, Row, Table, TableState};
+
+use crate::db::diff::{Change, ChangeKind};
+use crate::handler::{KeyHandler, scroll_nav};
+use crate::ui::{create_block, UIBlock, WidgetData};
+
+// Changes against an older feed, shown in place of the board. Choosing a trip opens it.
+pub struct DiffPane {
+    pub data: WidgetData<Change, Option<u32>, TableState>,
+    pub visible: bool,
+    // Feed compared against, empty without --compare
+    pub old_feed: String,
+}
+
+impl Default for DiffPane {
+    fn default() -> Self {
+        Self {
+            data: WidgetData::new(None),
+            visible: false,
+            old_feed: String::new(),
+        }
+    }
+}
+
+impl DiffPane {
+    pub fn set_changes(&mut self, old_feed: &str, changes: Vec<Change>) {
+        self.old_feed = old_feed.to_string();
+        self.data.set_items(changes);
+    }
+
+    // Only available in compare mode
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible && !self.old_feed.is_empty();
+    }
+}
+
+impl KeyHandler for DiffPane {
+    fn handle_key(&mut self, event: &KeyEvent) {
+        scroll_nav(&mut self.data, &event.code);
+    }
+}
+
+impl<'a> UIBlock<Table<'a>> for DiffPane {
+    fn build(&self, hovered: bool, selected: bool) -> Result<Table<'a>, Box<dyn Error>> {
+        let rows: Vec<Row> = self.data.items.iter()
+            .map(|c| Row::new(vec![
+                Cell::from(c.kind.name()).style(Style::default().fg(match c.kind {
+                    ChangeKind::ADDED => Color::Green,
+                    ChangeKind::REMOVED => Color::Red,
+                    ChangeKind::CHANGED => Color::Yellow,
+                })),
+                Cell::from(c.entity),
+                Cell::from(c.subject.to_string()),
+                Cell::from(c.detail.to_string()),
+            ]))
+            .collect();
+
+        let title = format!("Changes since {} ({})", self.old_feed, self.data.items.len());
+
+        Ok(Table::new(rows)
+            .style(Style::default().fg(Color::White))
+            .header(
+                Row::new(vec!["Change", "Entity", "Subject", "Detail"])
+                    .style(Style::default().add_modifier(Modifier::BOLD))
+            )
+            .highlight_style(Style::default().fg(Color::Magenta))
+            .highlight_symbol(">>")
+            .block(create_block(hovered, selected).title(title))
+            .widths(&[
+                Constraint::Length(8),
+                Constraint::Length(8),
+                Constraint::Percentage(40),
+                Constraint::Percentage(45),
+            ]))
+    }
+}
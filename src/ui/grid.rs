@@ -0,0 +1,153 @@
+use std::error::Error;
+
+use crossterm::event::{KeyCode, KeyEvent};
+use tui::buffer::Buffer;
+use tui::layout::Rect;
+use tui::style::{Color, Modifier, Style};
+use tui::widgets::{Block, Widget};
+
+use crate::db::grid::RouteGrid;
+use crate::handler::KeyHandler;
+use crate::ui::{create_block, UIBlock};
+
+const STOP_COLUMN_WIDTH: u16 = 18;
+const TIME_COLUMN_WIDTH: u16 = 6;
+
+#[derive(Default)]
+pub struct RouteGridView {
+    pub grid: Option<RouteGrid>,
+    // First visible trip column and stop row
+    pub column_offset: usize,
+    pub row_offset: usize,
+    // Trip the grid was opened from, highlighted
+    pub trip_id: Option<u32>,
+    // Route and direction shown, taken from the trip if not set
+    pub route: Option<(String, Option<u32>)>,
+    // Directions of the route, None for trips without direction_id
+    pub directions: Vec<Option<u32>>,
+    // Set when the grid has to be fetched
+    pub load: bool,
+    pub export_request: bool,
+    pub export_status: String,
+}
+
+impl RouteGridView {
+    pub fn set_grid(&mut self, grid: RouteGrid) {
+        // Start at the column of the trip the grid was opened from
+        self.column_offset = self.trip_id
+            .and_then(|id| grid.trips.iter().position(|t| t.trip_id == id))
+            .unwrap_or(0);
+        self.row_offset = 0;
+        self.grid = Some(grid);
+    }
+
+    // Switches to the next of the route's directions
+    fn reverse(&mut self) {
+        let directions = &self.directions;
+        if let Some((_, direction_id)) = &mut self.route {
+            let next = directions.iter()
+                .position(|d| d == direction_id)
+                .map(|i| directions[(i + 1) % directions.len()]);
+
+            if let Some(next) = next.filter(|d| d != direction_id) {
+                *direction_id = next;
+                self.load = true;
+            }
+        }
+    }
+
+    fn scroll(&mut self, columns: isize, rows: isize) {
+        if let Some(grid) = &self.grid {
+            self.column_offset = offset(self.column_offset, columns, grid.trips.len());
+            self.row_offset = offset(self.row_offset, rows, grid.stops.len());
+        }
+    }
+}
+
+fn offset(current: usize, delta: isize, len: usize) -> usize {
+    (current as isize + delta).clamp(0, len.saturating_sub(1) as isize) as usize
+}
+
+impl KeyHandler for RouteGridView {
+    fn handle_key(&mut self, event: &KeyEvent) {
+        match event.code {
+            KeyCode::Left | KeyCode::Char('h') => self.scroll(-1, 0),
+            KeyCode::Right | KeyCode::Char('l') => self.scroll(1, 0),
+            KeyCode::Up | KeyCode::Char('k') => self.scroll(0, -1),
+            KeyCode::Down | KeyCode::Char('j') => self.scroll(0, 1),
+            KeyCode::Home => self.column_offset = 0,
+            KeyCode::Char('d') => self.reverse(),
+            KeyCode::Char('C') => self.export_request = true,
+            _ => {}
+        }
+    }
+}
+
+impl<'a> UIBlock<GridView<'a>> for RouteGridView {
+    fn build(&self, hovered: bool, selected: bool) -> Result<GridView<'a>, Box<dyn Error>> {
+        let title = match &self.grid {
+            Some(grid) if self.export_status.is_empty() => grid.route_name.to_string(),
+            Some(grid) => format!("{} | {}", grid.route_name, self.export_status),
+            None => String::from("No route"),
+        };
+
+        Ok(GridView {
+            block: create_block(hovered, selected).title(title),
+            header: self.grid.iter()
+                .flat_map(|g| g.trips.iter().skip(self.column_offset))
+                .map(|t| (t.number.to_string(), self.trip_id == Some(t.trip_id)))
+                .collect(),
+            rows: self.grid.iter()
+                .flat_map(|g| g.stops.iter().zip(g.times.iter()).skip(self.row_offset))
+                .map(|(stop, times)| (
+                    stop.name.to_string(),
+                    times.iter().skip(self.column_offset).cloned().collect(),
+                ))
+                .collect(),
+        })
+    }
+}
+
+//region GridView
+// Visible part of the grid, drawn cell by cell since the column count varies
+pub struct GridView<'a> {
+    block: Block<'a>,
+    // Trip numbers, true for the highlighted trip
+    header: Vec<(String, bool)>,
+    rows: Vec<(String, Vec<Option<String>>)>,
+}
+
+impl<'a> Widget for GridView<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let inner = self.block.inner(area);
+        self.block.clone().render(area, buf);
+        if inner.width <= STOP_COLUMN_WIDTH || inner.height == 0 {
+            return;
+        }
+
+        let columns = ((inner.width - STOP_COLUMN_WIDTH) / TIME_COLUMN_WIDTH) as usize;
+        let column_x = |i: usize| inner.x + STOP_COLUMN_WIDTH + i as u16 * TIME_COLUMN_WIDTH;
+        let highlighted = |i: usize| self.header.get(i).is_some_and(|(_, h)| *h);
+
+        let bold = Style::default().add_modifier(Modifier::BOLD);
+        for (i, (number, _)) in self.header.iter().take(columns).enumerate() {
+            let style = if highlighted(i) { bold.fg(Color::Magenta) } else { bold };
+            buf.set_stringn(column_x(i), inner.y, number, TIME_COLUMN_WIDTH as usize - 1, style);
+        }
+
+        for (row, (name, times)) in self.rows.iter().take(inner.height as usize - 1).enumerate() {
+            let y = inner.y + 1 + row as u16;
+            buf.set_stringn(inner.x, y, name, STOP_COLUMN_WIDTH as usize - 1, Style::default());
+
+            for (i, time) in times.iter().take(columns).enumerate() {
+                let style = if highlighted(i) {
+                    Style::default().fg(Color::Magenta)
+                } else {
+                    Style::default()
+                };
+                buf.set_string(column_x(i), y, time.as_deref().unwrap_or("  |"), style);
+            }
+        }
+    }
+}
+//endregion
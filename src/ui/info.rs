@@ -0,0 +1,92 @@
+use tui::style::{Color, Modifier, Style};
+use tui::text::{Span, Spans, Text};
+use tui::widgets::{Block, Borders, Paragraph, Wrap};
+
+use crate::db::info::FeedSummary;
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+// Shown on startup to check the loaded dataset, toggled with 'f'
+pub struct FeedInfoPanel {
+    // Fetched when first shown
+    pub summary: Option<FeedSummary>,
+    pub visible: bool,
+}
+
+impl Default for FeedInfoPanel {
+    fn default() -> Self {
+        Self {
+            summary: None,
+            visible: true,
+        }
+    }
+}
+
+impl FeedInfoPanel {
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+    }
+}
+
+pub fn feed_info_popup<'a>(summary: &FeedSummary) -> Paragraph<'a> {
+    let heading = |text: &str| Spans::from(Span::styled(
+        text.to_string(),
+        Style::default().add_modifier(Modifier::BOLD),
+    ));
+    let mut lines: Vec<Spans> = Vec::new();
+
+    lines.push(heading("Feed"));
+    match &summary.feed_info {
+        Some(info) => {
+            lines.push(Spans::from(format!("Publisher: {} {}", info.publisher_name, info.publisher_url)));
+            lines.push(Spans::from(format!("Version: {}", info.version)));
+            if let (Some(start), Some(end)) = (info.start_date, info.end_date) {
+                lines.push(Spans::from(format!(
+                    "Published validity: {} - {}", start.format(DATE_FORMAT), end.format(DATE_FORMAT),
+                )));
+            }
+        }
+        None => lines.push(Spans::from(Span::styled(
+            "No feed_info.txt", Style::default().fg(Color::DarkGray),
+        ))),
+    }
+    lines.push(Spans::from(match summary.validity {
+        Some((first, last)) => format!(
+            "Service: {} - {}, median {} trips a day",
+            first.format(DATE_FORMAT), last.format(DATE_FORMAT), summary.median_trips,
+        ),
+        None => String::from("Service: no trips on any day"),
+    }));
+
+    lines.push(Spans::from(""));
+    lines.push(heading("Tables"));
+    for (table, count) in &summary.table_counts {
+        lines.push(Spans::from(format!("{:<18} {:>10}", table, count)));
+    }
+
+    lines.push(Spans::from(""));
+    lines.push(heading("Low service"));
+    if summary.low_service.is_empty() {
+        lines.push(Spans::from(Span::styled("None", Style::default().fg(Color::DarkGray))));
+    }
+    for range in &summary.low_service {
+        let dates = if range.first_date == range.last_date {
+            range.first_date.format(DATE_FORMAT).to_string()
+        } else {
+            format!("{} - {}", range.first_date.format(DATE_FORMAT), range.last_date.format(DATE_FORMAT))
+        };
+        lines.push(Spans::from(vec![
+            Span::styled(dates, Style::default().fg(Color::Yellow)),
+            Span::raw(format!(" min. {} trips", range.min_trips)),
+        ]));
+    }
+
+    Paragraph::new(Text::from(lines))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Cyan))
+                .title("Feed info (f to close)")
+        )
+        .wrap(Wrap { trim: false })
+}
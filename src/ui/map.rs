@@ -0,0 +1,223 @@
+use std::error::Error;
+
+use crossterm::event::{KeyCode, KeyEvent};
+use tui::buffer::Buffer;
+use tui::layout::Rect;
+use tui::style::{Color, Modifier, Style};
+use tui::widgets::{Block, ListState, Widget};
+
+use crate::db::types::Station;
+use crate::handler::{KeyHandler, scroll_nav};
+use crate::realtime::Vehicle;
+use crate::ui::{create_block, UIBlock, WidgetData};
+
+const DEFAULT_RADIUS_KM: f64 = 2.0;
+const MIN_RADIUS_KM: f64 = 0.25;
+const MAX_RADIUS_KM: f64 = 64.0;
+const KM_PER_LAT_DEGREE: f64 = 110.57;
+const KM_PER_LON_DEGREE: f64 = 111.32;
+
+pub struct Map {
+    pub data: WidgetData<Station, String, ListState>,
+    // (latitude, longitude) in the middle of the pane
+    pub center: (f64, f64),
+    // Distance from the center to the upper and lower border
+    pub radius_km: f64,
+    // Set whenever center or radius change and nearby stations have to be fetched
+    pub moved: bool,
+    // Realtime vehicle positions, the one running the selected trip is highlighted
+    pub vehicles: Vec<Vehicle>,
+    pub tracked_trip: Option<u32>,
+}
+
+impl Default for Map {
+    fn default() -> Self {
+        Self {
+            // Nothing chosen yet, the empty key must not replace the station selection
+            data: WidgetData { changed: false, ..WidgetData::new(String::new()) },
+            center: (0.0, 0.0),
+            radius_km: DEFAULT_RADIUS_KM,
+            moved: false,
+            vehicles: Vec::new(),
+            tracked_trip: None,
+        }
+    }
+}
+
+impl Map {
+    pub fn set_center(&mut self, latitude: f64, longitude: f64) {
+        self.center = (latitude, longitude);
+        self.moved = true;
+    }
+
+    // Follows the trip's vehicle, centering on it if it's running
+    pub fn track(&mut self, trip_id: u32) {
+        self.tracked_trip = Some(trip_id);
+        let position = self.vehicles.iter()
+            .find(|v| v.trip_id == trip_id)
+            .map(|v| (v.latitude, v.longitude));
+
+        if let Some((latitude, longitude)) = position {
+            self.set_center(latitude, longitude);
+        }
+    }
+
+    fn pan(&mut self, north: f64, east: f64) {
+        let step_km = self.radius_km / 2.0;
+        let (latitude, longitude) = self.center;
+
+        self.set_center(
+            latitude + north * step_km / KM_PER_LAT_DEGREE,
+            longitude + east * step_km / (KM_PER_LON_DEGREE * latitude.to_radians().cos()),
+        );
+    }
+
+    fn zoom(&mut self, factor: f64) {
+        self.radius_km = (self.radius_km * factor).clamp(MIN_RADIUS_KM, MAX_RADIUS_KM);
+        self.moved = true;
+    }
+}
+
+impl KeyHandler for Map {
+    fn handle_key(&mut self, event: &KeyEvent) {
+        match event.code {
+            KeyCode::Up => self.pan(1.0, 0.0),
+            KeyCode::Down => self.pan(-1.0, 0.0),
+            KeyCode::Left => self.pan(0.0, -1.0),
+            KeyCode::Right => self.pan(0.0, 1.0),
+            KeyCode::Char('+') => self.zoom(0.5),
+            KeyCode::Char('-') => self.zoom(2.0),
+            _ => scroll_nav(&mut self.data, &event.code),
+        }
+    }
+}
+
+impl<'a> UIBlock<MapView<'a>> for Map {
+    fn build(&self, hovered: bool, selected: bool) -> Result<MapView<'a>, Box<dyn Error>> {
+        let selected_index = self.data.state.selected();
+
+        Ok(MapView {
+            block: create_block(hovered, selected)
+                .title(format!("Map ({} km)", self.radius_km)),
+            center: self.center,
+            radius_km: self.radius_km,
+            // Stops without coordinates are left off the map
+            markers: self.data.items.iter().enumerate()
+                .filter_map(|(i, s)| s.location().map(|(latitude, longitude)| Marker {
+                    latitude,
+                    longitude,
+                    label: s.name.to_string(),
+                    kind: if selected_index == Some(i) { MarkerKind::SELECTED } else { MarkerKind::STATION },
+                }))
+                .chain(self.vehicles.iter().map(|v| Marker {
+                    latitude: v.latitude,
+                    longitude: v.longitude,
+                    label: v.label.to_string(),
+                    kind: if self.tracked_trip == Some(v.trip_id) {
+                        MarkerKind::TRACKED
+                    } else {
+                        MarkerKind::VEHICLE
+                    },
+                }))
+                .collect(),
+        })
+    }
+}
+
+//region MapView
+// Ordered by drawing priority, later kinds are drawn on top
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MarkerKind {
+    STATION,
+    VEHICLE,
+    SELECTED,
+    TRACKED,
+}
+
+impl MarkerKind {
+    fn symbol(&self) -> &'static str {
+        match self {
+            MarkerKind::STATION => "•",
+            MarkerKind::SELECTED => "◉",
+            MarkerKind::VEHICLE | MarkerKind::TRACKED => "■",
+        }
+    }
+
+    fn style(&self) -> Style {
+        match self {
+            MarkerKind::STATION => Style::default().fg(Color::White),
+            MarkerKind::VEHICLE => Style::default().fg(Color::Cyan),
+            MarkerKind::SELECTED => Style::default().fg(Color::Magenta).add_modifier(Modifier::BOLD),
+            MarkerKind::TRACKED => Style::default().fg(Color::LightGreen).add_modifier(Modifier::BOLD),
+        }
+    }
+}
+
+pub struct Marker {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub label: String,
+    pub kind: MarkerKind,
+}
+
+// Plots markers around a center using an equirectangular projection
+pub struct MapView<'a> {
+    block: Block<'a>,
+    center: (f64, f64),
+    radius_km: f64,
+    markers: Vec<Marker>,
+}
+
+impl<'a> MapView<'a> {
+    fn project(&self, area: &Rect, latitude: f64, longitude: f64) -> Option<(u16, u16)> {
+        if area.width == 0 || area.height == 0 {
+            return None;
+        }
+
+        // Terminal cells are about twice as high as wide
+        let km_per_row = self.radius_km / (f64::from(area.height) / 2.0);
+        let km_per_col = km_per_row / 2.0;
+
+        let (center_lat, center_lon) = self.center;
+        let north_km = (latitude - center_lat) * KM_PER_LAT_DEGREE;
+        let east_km = (longitude - center_lon) * KM_PER_LON_DEGREE * center_lat.to_radians().cos();
+
+        let col = f64::from(area.width) / 2.0 + east_km / km_per_col;
+        let row = f64::from(area.height) / 2.0 - north_km / km_per_row;
+
+        if col < 0.0 || row < 0.0 || col >= f64::from(area.width) || row >= f64::from(area.height) {
+            None
+        } else {
+            Some((area.x + col as u16, area.y + row as u16))
+        }
+    }
+}
+
+impl<'a> Widget for MapView<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let inner = self.block.inner(area);
+        self.block.clone().render(area, buf);
+
+        if let Some((x, y)) = self.project(&inner, self.center.0, self.center.1) {
+            buf.set_string(x, y, "+", Style::default().fg(Color::DarkGray));
+        }
+
+        // Selected and tracked markers last so their labels are never covered
+        let mut markers: Vec<&Marker> = self.markers.iter().collect();
+        markers.sort_by_key(|m| m.kind);
+
+        for marker in markers {
+            if let Some((x, y)) = self.project(&inner, marker.latitude, marker.longitude) {
+                let style = marker.kind.style();
+
+                buf.set_string(x, y, marker.kind.symbol(), style);
+
+                let label_width = (inner.x + inner.width).saturating_sub(x + 2);
+                if label_width > 0 {
+                    buf.set_stringn(x + 2, y, &marker.label, label_width as usize, style);
+                }
+            }
+        }
+    }
+}
+//endregion
@@ -1,12 +1,14 @@
+use std::collections::HashMap;
 use std::error::Error;
 
 use chrono::{Local, NaiveDate, NaiveTime, Duration};
 use tui::layout::Alignment;
 use tui::style::{Color, Modifier, Style};
-use tui::text::Text;
+use tui::text::{Span, Spans, Text};
 use tui::widgets::{List, ListItem, ListState, Paragraph};
 
-use crate::db::types::Station;
+use crate::db::types::{Route, route_type_name, Station, TripRun};
+use crate::realtime::AlertInfo;
 use crate::ui::{create_block, UIBlock, WidgetData};
 use crate::handler::{KeyHandler, scroll_nav};
 use crossterm::event::{KeyCode, KeyModifiers, KeyEvent};
@@ -14,26 +16,51 @@ use crossterm::event::{KeyCode, KeyModifiers, KeyEvent};
 //region StationList
 pub struct StationList {
     pub data: WidgetData<Station, String, ListState>,
+    // ServiceAlerts of the listed stations, keyed by stop_id
+    pub alerts: HashMap<String, Vec<AlertInfo>>,
+    pub show_alerts: bool,
 }
 
 impl Default for StationList {
     fn default() -> Self {
         Self {
             data: WidgetData::new(String::new()),
+            alerts: HashMap::new(),
+            show_alerts: false,
         }
     }
 }
 
+impl StationList {
+    pub fn selected_alerts(&self) -> &[AlertInfo] {
+        self.data.get_selected_item()
+            .and_then(|s| self.alerts.get(&s.stop_id))
+            .map_or(&[], |a| a.as_slice())
+    }
+}
+
 impl KeyHandler for StationList {
     fn handle_key(&mut self, event: &KeyEvent) {
-        scroll_nav(&mut self.data, &event.code);
+        match event.code {
+            KeyCode::Char('i') => self.show_alerts = !self.show_alerts,
+            _ => scroll_nav(&mut self.data, &event.code),
+        }
     }
 }
 
 impl<'a> UIBlock<List<'a>> for StationList {
     fn build(&self, hovered: bool, selected: bool) -> Result<List<'a>, Box<dyn Error>> {
         let items: Vec<ListItem> = self.data.items.iter()
-            .map(|s| ListItem::new(s.name.to_string()))
+            .map(|s| {
+                if self.alerts.get(&s.stop_id).is_some_and(|a| !a.is_empty()) {
+                    ListItem::new(Spans::from(vec![
+                        Span::raw(s.display_name()),
+                        Span::styled(" !", Style::default().fg(Color::Yellow)),
+                    ]))
+                } else {
+                    ListItem::new(s.display_name())
+                }
+            })
             .collect();
 
         Ok(List::new(items)
@@ -50,6 +77,176 @@ impl<'a> UIBlock<List<'a>> for StationList {
 }
 //endregion
 
+//region TripSearch
+// Runs of a train number, shown instead of the station list
+pub struct TripSearch {
+    pub data: WidgetData<TripRun, u32, ListState>,
+    // Set while the search input is a trip search
+    pub active: bool,
+}
+
+impl Default for TripSearch {
+    fn default() -> Self {
+        Self {
+            data: WidgetData::new(0),
+            active: false,
+        }
+    }
+}
+
+impl KeyHandler for TripSearch {
+    fn handle_key(&mut self, event: &KeyEvent) {
+        scroll_nav(&mut self.data, &event.code);
+    }
+}
+
+impl<'a> UIBlock<List<'a>> for TripSearch {
+    fn build(&self, hovered: bool, selected: bool) -> Result<List<'a>, Box<dyn Error>> {
+        let items: Vec<ListItem> = self.data.items.iter()
+            .map(|r| {
+                let style = if r.runs_on_date {
+                    Style::default()
+                } else {
+                    Style::default().fg(Color::DarkGray)
+                };
+
+                ListItem::new(Text::from(vec![
+                    Spans::from(Span::styled(format!("{} {} {}", r.number, r.departure(), r.operating_days), style)),
+                    Spans::from(Span::styled(format!("{} → {}", r.origin, r.destination), style)),
+                ]))
+            })
+            .collect();
+
+        Ok(List::new(items)
+            .block(create_block(hovered, selected).title("Trips"))
+            .style(Style::default().fg(Color::White))
+            .highlight_symbol(">>")
+            .highlight_style(
+                Style::default()
+                    .fg(Color::Magenta)
+                    .add_modifier(Modifier::BOLD)
+            )
+        )
+    }
+}
+//endregion
+
+//region RouteList
+// Routes matching the search, shown instead of the station list
+pub struct RouteList {
+    pub data: WidgetData<Route, String, ListState>,
+    // Unfiltered search result
+    pub routes: Vec<Route>,
+    pub agency: Option<String>,
+    pub route_type: Option<i32>,
+    // Set while the search input is a route search
+    pub active: bool,
+}
+
+impl Default for RouteList {
+    fn default() -> Self {
+        Self {
+            data: WidgetData::new(String::new()),
+            routes: Vec::new(),
+            agency: None,
+            route_type: None,
+            active: false,
+        }
+    }
+}
+
+impl RouteList {
+    pub fn set_routes(&mut self, routes: Vec<Route>) {
+        self.routes = routes;
+        // Filters not matching any route anymore are dropped
+        if !self.routes.iter().any(|r| Some(&r.agency) == self.agency.as_ref()) {
+            self.agency = None;
+        }
+        if !self.routes.iter().any(|r| Some(r.route_type) == self.route_type) {
+            self.route_type = None;
+        }
+        self.apply_filters();
+    }
+
+    fn apply_filters(&mut self) {
+        let items = self.routes.iter()
+            .filter(|r| self.agency.as_ref().is_none_or(|a| &r.agency == a))
+            .filter(|r| self.route_type.is_none_or(|t| r.route_type == t))
+            .cloned()
+            .collect();
+        self.data.set_items(items);
+    }
+
+    // Steps through the values of the routes, then back to no filter
+    fn cycle_agency(&mut self) {
+        let mut agencies: Vec<String> = self.routes.iter().map(|r| r.agency.to_string()).collect();
+        agencies.sort();
+        agencies.dedup();
+        self.agency = next_filter(&agencies, self.agency.as_ref()).cloned();
+        self.apply_filters();
+    }
+
+    fn cycle_route_type(&mut self) {
+        let mut route_types: Vec<i32> = self.routes.iter().map(|r| r.route_type).collect();
+        route_types.sort_unstable();
+        route_types.dedup();
+        self.route_type = next_filter(&route_types, self.route_type.as_ref()).copied();
+        self.apply_filters();
+    }
+}
+
+fn next_filter<'a, T: PartialEq>(values: &'a [T], current: Option<&T>) -> Option<&'a T> {
+    match current.and_then(|c| values.iter().position(|v| v == c)) {
+        Some(i) => values.get(i + 1),
+        None => values.first(),
+    }
+}
+
+impl KeyHandler for RouteList {
+    fn handle_key(&mut self, event: &KeyEvent) {
+        match event.code {
+            KeyCode::Char('a') => self.cycle_agency(),
+            KeyCode::Char('t') => self.cycle_route_type(),
+            _ => scroll_nav(&mut self.data, &event.code),
+        }
+    }
+}
+
+impl<'a> UIBlock<List<'a>> for RouteList {
+    fn build(&self, hovered: bool, selected: bool) -> Result<List<'a>, Box<dyn Error>> {
+        let items: Vec<ListItem> = self.data.items.iter()
+            .map(|r| ListItem::new(Text::from(vec![
+                Spans::from(r.display_name()),
+                Spans::from(Span::styled(
+                    format!("{} · {}", r.agency, route_type_name(r.route_type)),
+                    Style::default().fg(Color::DarkGray),
+                )),
+            ])))
+            .collect();
+
+        let filters: Vec<String> = self.agency.iter().cloned()
+            .chain(self.route_type.map(|t| route_type_name(t).to_string()))
+            .collect();
+        let title = if filters.is_empty() {
+            String::from("Routes")
+        } else {
+            format!("Routes ({})", filters.join(", "))
+        };
+
+        Ok(List::new(items)
+            .block(create_block(hovered, selected).title(title))
+            .style(Style::default().fg(Color::White))
+            .highlight_symbol(">>")
+            .highlight_style(
+                Style::default()
+                    .fg(Color::Magenta)
+                    .add_modifier(Modifier::BOLD)
+            )
+        )
+    }
+}
+//endregion
+
 //region DateSelection
 pub struct DateSelection {
     pub date: NaiveDate,
@@ -3,21 +3,47 @@ use std::error::Error;
 use tui::backend::Backend;
 use tui::Frame;
 use tui::layout::{Constraint, Direction, Layout, Rect};
-use tui::style::{Color, Style};
-use tui::widgets::{Block, Borders, ListState, TableState, Widget};
+use tui::style::{Color, Modifier, Style};
+use tui::widgets::{Block, Borders, Cell, Clear, ListState, TableState, Widget};
 
-use crate::db::types::{WidgetItem, BoardType, DisplayStop};
+use crate::db::diff::Change;
+use crate::db::types::{AgencyStats, DisplayStop, Route, Station, TripRun};
 use crate::ui::board::Board;
-use crate::ui::menu::{DateSelection, Search, StationList, TimeSelection};
+use crate::ui::menu::{DateSelection, RouteList, Search, StationList, TimeSelection, TripSearch};
 use crate::ui::SelectableBlock::*;
 use crate::handler::KeyHandler;
-use crate::ui::trip::Trip;
+use crate::export;
+use crate::ui::trip::{Trip, TripView};
+use crate::ui::map::Map;
+use crate::ui::agency::AgencyPane;
+use crate::ui::diff::DiffPane;
+use crate::ui::info::{feed_info_popup, FeedInfoPanel};
 use crossterm::event::{KeyEvent, KeyCode};
 use crate::db::GTFSDatabase;
+use crate::db::spatial::parse_coordinates;
+use crate::realtime::{AlertInfo, AlertScope, Realtime};
+use crate::ui::alert::{alert_popup, popup_area};
+use crate::realtime::proto::FeedMessage;
+use chrono::NaiveDate;
 
 pub mod menu;
 pub mod board;
 pub mod trip;
+pub mod map;
+pub mod calendar;
+pub mod alert;
+pub mod grid;
+pub mod agency;
+pub mod info;
+pub mod diff;
+
+// Search input prefix for a coordinate search, e.g. "@52.52,13.40"
+pub const COORDINATE_PREFIX: char = '@';
+// Search input prefix for a train number search, e.g. "#ICE 598"
+pub const TRIP_PREFIX: char = '#';
+// Search input prefix for a route search, e.g. "/RE" or "/" for all routes
+pub const ROUTE_PREFIX: char = '/';
+const NEARBY_RADIUS_KM: f64 = 5.0;
 
 pub trait UIBlock<T>
     where T: Widget
@@ -25,6 +51,49 @@ pub trait UIBlock<T>
     fn build(&self, hovered: bool, selected: bool) -> Result<T, Box<dyn Error>>;
 }
 
+//region WidgetItem
+// Key an item is selected by, kept across list updates
+pub trait WidgetItem<T> {
+    fn to_val(&self) -> T;
+}
+
+impl WidgetItem<String> for Station {
+    fn to_val(&self) -> String {
+        self.stop_id.to_string()
+    }
+}
+
+impl WidgetItem<u32> for DisplayStop {
+    fn to_val(&self) -> u32 {
+        self.trip_id
+    }
+}
+
+impl WidgetItem<u32> for TripRun {
+    fn to_val(&self) -> u32 {
+        self.trip_id
+    }
+}
+
+impl WidgetItem<String> for Route {
+    fn to_val(&self) -> String {
+        self.route_id.to_string()
+    }
+}
+
+impl WidgetItem<String> for AgencyStats {
+    fn to_val(&self) -> String {
+        self.agency_id.to_string()
+    }
+}
+
+impl WidgetItem<Option<u32>> for Change {
+    fn to_val(&self) -> Option<u32> {
+        self.trip_id
+    }
+}
+//endregion
+
 //region WidgetData
 pub struct WidgetData<T, K, S>
     where T: WidgetItem<K>, S: WidgetState
@@ -53,18 +122,19 @@ impl<T, K, S> WidgetData<T, K, S>
         self.state = S::default();
     }
 
+    // Like set_items, but keeps the selection for refreshed rows
+    pub fn replace_items(&mut self, items: Vec<T>) {
+        let selected = self.state.selected().filter(|i| *i < items.len());
+        self.items = items;
+        self.state.select(selected);
+    }
+
     //todo handle empty list case
     pub fn next(&mut self) {
         self.state.select(Some(
             match self.state.selected() {
-                Some(i) => {
-                    if i < self.items.len() - 1 {
-                        i + 1
-                    } else {
-                        0
-                    }
-                }
-                None => 0,
+                Some(i) if i < self.items.len() - 1 => i + 1,
+                _ => 0,
             }
         ));
     }
@@ -94,7 +164,7 @@ impl<T, K, S> WidgetData<T, K, S>
     }
 
     pub fn get_selected_item(&self) -> Option<&T> {
-        self.state.selected().map_or(None, |i| self.items.get(i))
+        self.state.selected().and_then(|i| self.items.get(i))
     }
 
     pub fn update(&mut self) {
@@ -115,6 +185,7 @@ pub enum SelectableBlock {
     TIME,
     BOARD,
     TRIP,
+    MAP,
 }
 
 impl SelectableBlock {
@@ -125,32 +196,34 @@ impl SelectableBlock {
             DATE => TIME,
             TIME => BOARD,
             BOARD => TRIP,
-            TRIP => SEARCH,
+            TRIP => MAP,
+            MAP => SEARCH,
         }
     }
 
     pub fn prev(&self) -> SelectableBlock {
         match self {
-            SEARCH => TRIP,
+            SEARCH => MAP,
             STATION => SEARCH,
             DATE => STATION,
             TIME => DATE,
             BOARD => TIME,
             TRIP => BOARD,
+            MAP => TRIP,
         }
     }
 
     pub fn right(&self) -> SelectableBlock {
         match self {
             BOARD => TRIP,
-            TRIP => SEARCH,
+            TRIP | MAP => SEARCH,
             _ => BOARD,
         }
     }
 
     pub fn left(&self) -> SelectableBlock {
         match self {
-            TRIP => BOARD,
+            TRIP | MAP => BOARD,
             BOARD => SEARCH,
             _ => TRIP,
         }
@@ -196,10 +269,22 @@ pub struct App {
     pub date_selection: DateSelection,
     pub time_selection: TimeSelection,
     pub station_list: StationList,
+    pub trip_search: TripSearch,
+    pub route_list: RouteList,
 
     pub board: Board,
+    pub agencies: AgencyPane,
+    pub diff: DiffPane,
+    pub feed_info: FeedInfoPanel,
 
     pub trip: Trip,
+    pub map: Map,
+
+    pub realtime: Realtime,
+    realtime_changed: bool,
+    // Board time follows the timestamps of replayed snapshots
+    pub replay: bool,
+    pub export_dir: String,
 }
 
 impl App {
@@ -212,23 +297,101 @@ impl App {
             date_selection: DateSelection::default(),
             time_selection: TimeSelection::default(),
             station_list: StationList::default(),
+            trip_search: TripSearch::default(),
+            route_list: RouteList::default(),
             board: Board::default(),
+            agencies: AgencyPane::default(),
+            diff: DiffPane::default(),
+            feed_info: FeedInfoPanel::default(),
             trip: Trip::default(),
+            map: Map::default(),
+            realtime: Realtime::default(),
+            realtime_changed: false,
+            replay: false,
+            export_dir: String::new(),
+        }
+    }
+
+    pub fn update_realtime(&mut self, feed: Result<FeedMessage, String>) {
+        let db = &self.db;
+        let updated = match feed {
+            Ok(feed) => self.realtime.update(feed, |trip_id| db.fetch_trip(trip_id).unwrap_or_default()),
+            Err(e) => Err(e),
+        };
+        match updated {
+            Ok(()) => {
+                self.map.vehicles = self.realtime.vehicles();
+                let status_format = if self.replay { "Replay %Y-%m-%d %H:%M:%S" } else { "Realtime %H:%M:%S" };
+                self.board.realtime_status = self.realtime.timestamp
+                    .map(|t| t.format(status_format).to_string())
+                    .unwrap_or_else(|| String::from("Realtime"));
+
+                if let (true, Some(t)) = (self.replay, self.realtime.timestamp) {
+                    let replay_dt = t.naive_local();
+                    self.date_selection.date = replay_dt.date();
+                    self.time_selection.time = replay_dt.time();
+                    self.station_list.data.changed = true;
+                }
+            }
+            Err(e) => self.board.realtime_status = format!("Realtime error: {}", e),
         }
+        self.realtime_changed = true;
+    }
+
+    fn load_trip(&mut self, trip_id: u32, service_date: NaiveDate) -> Result<(), Box<dyn Error>> {
+        self.trip.stops = self.db.fetch_trip(trip_id)?;
+        self.trip.service_date = service_date;
+        let service = self.trip.stops.first()
+            .and_then(|s| self.db.get_service(s.service_id))
+            .cloned();
+
+        self.trip.operating_days = service.as_ref()
+            .map_or(String::new(), |s| s.operating_days());
+        self.trip.calendar.set_service(service, self.date_selection.date);
+        self.trip.data.set_items(self.trip.display_stops(&self.realtime));
+        self.map.track(trip_id);
+
+        Ok(())
+    }
+
+    // Name of a listed station, the stop_id if it is not listed anymore
+    fn station_name(&self, stop_id: &str) -> String {
+        self.station_list.data.items.iter()
+            .chain(self.map.data.items.iter())
+            .find(|s| s.stop_id == stop_id)
+            .map_or(stop_id.to_string(), |s| s.name.to_string())
+    }
+
+    fn refresh_station_alerts(&mut self) {
+        // Alerts active at the board time, which follows the clock during a replay
+        let date_time = self.date_selection.date.and_time(self.time_selection.time);
+        let realtime = &self.realtime;
+
+        self.station_list.alerts = self.station_list.data.items.iter()
+            .map(|s| {
+                let scope = AlertScope { stop_id: Some(&s.stop_id), ..AlertScope::default() };
+                (s.stop_id.to_string(), realtime.alerts(&scope, date_time))
+            })
+            .collect();
     }
 
-    pub fn key_handler(&mut self) -> Box<&mut dyn KeyHandler> {
+    pub fn key_handler(&mut self) -> &mut dyn KeyHandler {
         if let Some(b) = self.block_focused {
-            Box::new(match b {
+            match b {
                 SelectableBlock::SEARCH => &mut self.search,
+                SelectableBlock::STATION if self.trip_search.active => &mut self.trip_search,
+                SelectableBlock::STATION if self.route_list.active => &mut self.route_list,
                 SelectableBlock::STATION => &mut self.station_list,
                 SelectableBlock::DATE => &mut self.date_selection,
                 SelectableBlock::TIME => &mut self.time_selection,
+                SelectableBlock::BOARD if self.diff.visible => &mut self.diff,
+                SelectableBlock::BOARD if self.agencies.visible => &mut self.agencies,
                 SelectableBlock::BOARD => &mut self.board,
                 SelectableBlock::TRIP => &mut self.trip,
-            })
+                SelectableBlock::MAP => &mut self.map,
+            }
         } else {
-            Box::new(self)
+            self
         }
     }
 
@@ -237,34 +400,177 @@ impl App {
     ) -> Result<(), Box<dyn Error>>
         where B: Backend
     {
+        //TripSearch
+        if self.search.changed {
+            let number = self.search.input.strip_prefix(TRIP_PREFIX);
+            self.trip_search.active = number.is_some();
+            if let Some(number) = number {
+                let runs = self.db.find_trips(number, &self.date_selection.date)?;
+                self.trip_search.data.set_items(runs);
+                self.search.changed = false;
+            }
+        }
+
+        //Agency chosen in the agency pane lists its routes
+        if self.agencies.data.changed {
+            if let Some(name) = self.agencies.selected_name() {
+                self.search.input = ROUTE_PREFIX.to_string();
+                self.search.changed = true;
+                self.route_list.agency = Some(name);
+            }
+            self.agencies.data.changed = false;
+        }
+
+        //RouteList
+        if self.search.changed {
+            let name = self.search.input.strip_prefix(ROUTE_PREFIX);
+            self.route_list.active = name.is_some();
+            if let Some(name) = name {
+                let routes = self.db.fetch_routes(name.trim())?;
+                self.route_list.set_routes(routes);
+                self.search.changed = false;
+            }
+        }
+
         //StationList
         if self.search.changed {
-            self.station_list.data.set_items(
-                self.db.fetch_stations(&self.search.input)?
-            );
+            let position = self.search.input.strip_prefix(COORDINATE_PREFIX)
+                .and_then(parse_coordinates);
+
+            let stations = match position {
+                Some((lat, lon)) => {
+                    self.map.set_center(lat, lon);
+                    self.db.fetch_nearby_stations(lat, lon, NEARBY_RADIUS_KM)
+                }
+                None => self.db.fetch_stations(&self.search.input)?,
+            };
+            self.station_list.data.set_items(stations);
+            self.refresh_station_alerts();
             self.search.changed = false;
         }
 
         let selected_dt = self.date_selection.date.and_time(self.time_selection.time);
 
+        //Map selection loads the board of the chosen station
+        if self.map.data.changed {
+            self.station_list.data.key = self.map.data.key.to_string();
+            self.station_list.data.changed = true;
+            self.map.data.changed = false;
+        }
+
         //Board
         if self.station_list.data.changed {
-            let stops = self.db.fetch_stops(
+            if let Some((lat, lon)) = self.db.fetch_station_location(&self.station_list.data.key)? {
+                self.map.set_center(lat, lon);
+            }
+
+            self.board.stops = self.db.fetch_stops(
                 &self.station_list.data.key,
-                BoardType::DEPARTURE,
+                self.board.board_type,
                 selected_dt
-            )?.iter().map(|s| DisplayStop::from(s, selected_dt)).collect();
-            self.board.data.set_items(stops);
+            )?;
+            self.board.station = self.station_name(&self.station_list.data.key);
+            self.board.date_time = selected_dt;
+            self.board.data.set_items(self.board.display_stops(&self.realtime));
             self.station_list.data.changed = false;
         }
 
         if self.board.data.changed {
-            let stops = self.db.fetch_trip(self.board.data.key)?.iter()
-                .map(|s| DisplayStop::from(s, selected_dt)).collect();
-            self.trip.data.set_items(stops);
+            let service_date = self.board.data.get_selected_item()
+                .map_or(selected_dt.date(), |s| s.service_date);
+            self.load_trip(self.board.data.key, service_date)?;
             self.board.data.changed = false;
         }
 
+        //Trip chosen in the trip search
+        if self.trip_search.data.changed {
+            if self.trip_search.data.get_selected_item().is_some() {
+                self.load_trip(self.trip_search.data.key, selected_dt.date())?;
+            }
+            self.trip_search.data.changed = false;
+        }
+
+        //Trip chosen in the diff pane, removed ones are not in this feed
+        if self.diff.data.changed {
+            if let Some(trip_id) = self.diff.data.key {
+                // Changed trips may not run on the board date, opened on the nearest date they do
+                let service_date = self.db.fetch_trip(trip_id)?.first()
+                    .and_then(|s| self.db.nearest_service_date(s.service_id, &selected_dt.date()))
+                    .unwrap_or_else(|| selected_dt.date());
+                self.load_trip(trip_id, service_date)?;
+            }
+            self.diff.data.changed = false;
+        }
+
+        //Route chosen in the route list, shown as grid starting with its first direction
+        if self.route_list.data.changed {
+            if self.route_list.data.get_selected_item().is_some() {
+                let route_id = self.route_list.data.key.to_string();
+                let direction_id = self.db.fetch_route_directions(&route_id)?
+                    .into_iter().next().flatten();
+                self.trip.grid.route = Some((route_id, direction_id));
+                self.trip.grid.trip_id = None;
+                self.trip.grid.load = true;
+                self.trip.view = TripView::GRID;
+            }
+            self.route_list.data.changed = false;
+        }
+
+        //Route grid, of the trip's route unless chosen otherwise
+        if self.trip.grid.load {
+            let trip_id = self.trip.stops.first().map(|s| s.trip_id);
+            if self.trip.grid.route.is_none() {
+                self.trip.grid.trip_id = trip_id;
+                self.trip.grid.route = match trip_id {
+                    Some(id) => self.db.fetch_trip_route(id)?,
+                    None => None,
+                };
+            }
+
+            self.trip.grid.grid = None;
+            if let Some((route_id, direction_id)) = &self.trip.grid.route {
+                self.trip.grid.directions = self.db.fetch_route_directions(route_id)?;
+                let grid = self.db.fetch_route_grid(route_id, *direction_id, &self.date_selection.date)?;
+                self.trip.grid.set_grid(grid);
+            }
+            self.trip.grid.load = false;
+        }
+
+        //Export
+        if self.trip.grid.export_request {
+            if let Some(grid) = &self.trip.grid.grid {
+                self.trip.grid.export_status = match export::write_route_grid(grid, &self.export_dir) {
+                    Ok(path) => format!("Exported {}", path.display()),
+                    Err(e) => format!("Export failed: {}", e),
+                };
+            }
+            self.trip.grid.export_request = false;
+        }
+
+        if let Some(format) = self.board.export_request.take() {
+            self.board.export_status = match export::write_board(&self.board, format, &self.export_dir) {
+                Ok(path) => format!("Exported {}", path.display()),
+                Err(e) => format!("Export failed: {}", e),
+            };
+        }
+
+        //Realtime
+        if self.realtime_changed {
+            self.board.data.replace_items(self.board.display_stops(&self.realtime));
+            self.trip.data.replace_items(self.trip.display_stops(&self.realtime));
+            self.refresh_station_alerts();
+            self.realtime_changed = false;
+        }
+
+        //Map
+        if self.map.moved {
+            let (lat, lon) = self.map.center;
+            self.map.data.set_items(
+                self.db.fetch_nearby_stations(lat, lon, self.map.radius_km * 2.0)
+            );
+            self.map.moved = false;
+        }
+
         //Left: Menu
         let menu_layout = Layout::default()
             .direction(Direction::Vertical)
@@ -300,33 +606,140 @@ impl App {
             menu_layout[3],
         );
 
-        frame.render_stateful_widget(
-            self.station_list.build(
-                self.block_hover == SelectableBlock::STATION,
-                self.block_focused == Some(SelectableBlock::STATION),
-            )?,
-            menu_layout[1],
-            &mut self.station_list.data.state,
-        );
+        if self.trip_search.active {
+            frame.render_stateful_widget(
+                self.trip_search.build(
+                    self.block_hover == SelectableBlock::STATION,
+                    self.block_focused == Some(SelectableBlock::STATION),
+                )?,
+                menu_layout[1],
+                &mut self.trip_search.data.state,
+            );
+        } else if self.route_list.active {
+            frame.render_stateful_widget(
+                self.route_list.build(
+                    self.block_hover == SelectableBlock::STATION,
+                    self.block_focused == Some(SelectableBlock::STATION),
+                )?,
+                menu_layout[1],
+                &mut self.route_list.data.state,
+            );
+        } else {
+            frame.render_stateful_widget(
+                self.station_list.build(
+                    self.block_hover == SelectableBlock::STATION,
+                    self.block_focused == Some(SelectableBlock::STATION),
+                )?,
+                menu_layout[1],
+                &mut self.station_list.data.state,
+            );
+        }
 
-        //Center: Board
-        frame.render_stateful_widget(
-            self.board.build(
-                self.block_hover == SelectableBlock::BOARD,
-                self.block_focused == Some(SelectableBlock::BOARD),
-            )?,
-            layout[1],
-            &mut self.board.data.state,
-        );
+        //Center: Board, changes or agencies
+        if self.diff.visible {
+            frame.render_stateful_widget(
+                self.diff.build(
+                    self.block_hover == SelectableBlock::BOARD,
+                    self.block_focused == Some(SelectableBlock::BOARD),
+                )?,
+                layout[1],
+                &mut self.diff.data.state,
+            );
+        } else if self.agencies.visible {
+            if self.agencies.date != Some(self.date_selection.date) {
+                self.agencies.data.replace_items(self.db.fetch_agency_stats(&self.date_selection.date)?);
+                self.agencies.date = Some(self.date_selection.date);
+            }
+
+            frame.render_stateful_widget(
+                self.agencies.build(
+                    self.block_hover == SelectableBlock::BOARD,
+                    self.block_focused == Some(SelectableBlock::BOARD),
+                )?,
+                layout[1],
+                &mut self.agencies.data.state,
+            );
+        } else {
+            frame.render_stateful_widget(
+                self.board.build(
+                    self.block_hover == SelectableBlock::BOARD,
+                    self.block_focused == Some(SelectableBlock::BOARD),
+                )?,
+                layout[1],
+                &mut self.board.data.state,
+            );
+        }
+
+        //Alert popups
+        let popup_alerts = match self.block_focused {
+            Some(SelectableBlock::BOARD) if self.board.show_alerts
+                && !self.agencies.visible && !self.diff.visible => self.board.data
+                .get_selected_item()
+                .map(|s| s.alerts.as_slice()),
+            Some(SelectableBlock::STATION) if self.station_list.show_alerts
+                && !self.trip_search.active && !self.route_list.active => {
+                Some(self.station_list.selected_alerts())
+            }
+            _ => None,
+        };
+        if let Some(alerts) = popup_alerts {
+            let area = popup_area(layout[1]);
+            frame.render_widget(Clear, area);
+            frame.render_widget(alert_popup(alerts), area);
+        }
 
-        //Right: Trip
-        frame.render_stateful_widget(
-            self.trip.build(
-                self.block_hover == SelectableBlock::TRIP,
-                self.block_focused == Some(SelectableBlock::TRIP)
+        //Feed info
+        if self.feed_info.visible {
+            if self.feed_info.summary.is_none() {
+                self.feed_info.summary = Some(self.db.fetch_feed_summary()?);
+            }
+            if let Some(summary) = &self.feed_info.summary {
+                let area = popup_area(layout[1]);
+                frame.render_widget(Clear, area);
+                frame.render_widget(feed_info_popup(summary), area);
+            }
+        }
+
+        //Right: Trip and map
+        let detail_layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Percentage(60),
+                Constraint::Percentage(40),
+            ])
+            .split(layout[2]);
+
+        match self.trip.view {
+            TripView::CALENDAR => frame.render_widget(
+                self.trip.calendar.build(
+                    self.block_hover == SelectableBlock::TRIP,
+                    self.block_focused == Some(SelectableBlock::TRIP)
+                )?,
+                detail_layout[0],
+            ),
+            TripView::GRID => frame.render_widget(
+                self.trip.grid.build(
+                    self.block_hover == SelectableBlock::TRIP,
+                    self.block_focused == Some(SelectableBlock::TRIP)
+                )?,
+                detail_layout[0],
+            ),
+            TripView::STOPS => frame.render_stateful_widget(
+                self.trip.build(
+                    self.block_hover == SelectableBlock::TRIP,
+                    self.block_focused == Some(SelectableBlock::TRIP)
+                )?,
+                detail_layout[0],
+                &mut self.trip.data.state,
+            ),
+        }
+
+        frame.render_widget(
+            self.map.build(
+                self.block_hover == SelectableBlock::MAP,
+                self.block_focused == Some(SelectableBlock::MAP),
             )?,
-            layout[2],
-            &mut self.trip.data.state,
+            detail_layout[1],
         );
 
         Ok(())
@@ -353,12 +766,40 @@ impl KeyHandler for App {
             KeyCode::Enter => {
                 self.block_focused = Some(self.block_hover);
             }
+            KeyCode::Char('a') => self.agencies.toggle(),
+            KeyCode::Char('f') => self.feed_info.toggle(),
+            KeyCode::Char('c') => self.diff.toggle(),
             _ => {}
         }
     }
 }
 //endregion
 
+// Realtime delay in minutes, colored by severity
+fn delay_cell<'a>(delay: Option<i64>) -> Cell<'a> {
+    match delay {
+        Some(d) => Cell::from(format!("{:+}", d)).style(Style::default().fg(
+            if d < 1 {
+                Color::Green
+            } else if d < 6 {
+                Color::Yellow
+            } else {
+                Color::Red
+            }
+        )),
+        None => Cell::from(""),
+    }
+}
+
+// Marks rows affected by ServiceAlerts
+fn alert_cell<'a>(alerts: &[AlertInfo]) -> Cell<'a> {
+    if alerts.is_empty() {
+        Cell::from("")
+    } else {
+        Cell::from("!").style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))
+    }
+}
+
 //todo improve matching, replace with bitflag
 fn create_block<'a>(hovered: bool, selected: bool) -> Block<'a> {
     Block::default()
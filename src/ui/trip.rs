@@ -1,55 +1,120 @@
 use std::error::Error;
 
+use chrono::{Duration, Local, NaiveDate};
 use tui::layout::Constraint;
 use tui::style::{Color, Modifier, Style};
-use tui::widgets::{Row, Table, TableState};
+use tui::widgets::{Cell, Row, Table, TableState};
 
-use crate::db::types::DisplayStop;
-use crate::ui::{UIBlock, WidgetData, create_block};
+use crate::db::types::{BoardType, DisplayStop, Stop};
+use crate::realtime::Realtime;
+use crate::ui::{UIBlock, WidgetData, create_block, delay_cell};
+use crate::ui::calendar::ServiceCalendar;
+use crate::ui::grid::RouteGridView;
 use crate::handler::{KeyHandler, scroll_nav};
-use crossterm::event::KeyEvent;
+use crossterm::event::{KeyCode, KeyEvent};
+
+// What the trip pane shows, the stop list unless toggled
+#[derive(Copy, Clone, PartialEq)]
+pub enum TripView {
+    STOPS,
+    CALENDAR,
+    GRID,
+}
 
 pub struct Trip {
     pub data: WidgetData<DisplayStop, u32, TableState>,
+    // Scheduled stops of the trip and the day it started on
+    pub stops: Vec<Stop>,
+    pub service_date: NaiveDate,
+    pub calendar: ServiceCalendar,
+    pub grid: RouteGridView,
+    pub view: TripView,
+    // Operating days of the trip's service, shown in the header
+    pub operating_days: String,
 }
 
 impl Default for Trip {
     fn default() -> Self {
         Self {
             data: WidgetData::new(0),
+            stops: Vec::new(),
+            service_date: Local::today().naive_local(),
+            calendar: ServiceCalendar::default(),
+            grid: RouteGridView::default(),
+            view: TripView::STOPS,
+            operating_days: String::new(),
         }
     }
 }
 
+impl Trip {
+    pub fn display_stops(&self, realtime: &Realtime) -> Vec<DisplayStop> {
+        self.stops.iter()
+            .map(|s| {
+                // Stops past midnight are shown on the following day
+                let dt = (self.service_date + Duration::days(s.day_offset()))
+                    .and_hms(0, 0, 0);
+                DisplayStop::from(s, &BoardType::DEPARTURE, self.service_date, dt, &realtime.predict(s, self.service_date))
+            })
+            .collect()
+    }
+}
+
 impl KeyHandler for Trip {
     fn handle_key(&mut self, event: &KeyEvent) {
-        scroll_nav(&mut self.data, &event.code);
+        match (event.code, self.view) {
+            (KeyCode::Char('c'), TripView::CALENDAR) | (KeyCode::Char('g'), TripView::GRID) => {
+                self.view = TripView::STOPS;
+            }
+            (KeyCode::Char('c'), _) => self.view = TripView::CALENDAR,
+            (KeyCode::Char('g'), _) => {
+                self.view = TripView::GRID;
+                self.grid.route = None;
+                self.grid.load = true;
+            }
+            (_, TripView::CALENDAR) => self.calendar.handle_key(event),
+            (_, TripView::GRID) => self.grid.handle_key(event),
+            (code, TripView::STOPS) => scroll_nav(&mut self.data, &code),
+        }
     }
 }
 
 impl<'a> UIBlock<Table<'a>> for Trip {
     fn build(&self, hovered: bool, selected: bool) -> Result<Table<'a>, Box<dyn Error>> {
         let rows: Vec<Row> = self.data.items.iter()
-            .map(|s| Row::new(vec![
-                s.head_sign.to_string(),
-                s.arr_time.to_string(),
-                s.dep_time.to_string(),
-            ]))
+            .map(|s| {
+                if s.skipped {
+                    Row::new(vec![
+                        Cell::from(s.stop_name.to_string()),
+                        Cell::from(s.arr_time.to_string()),
+                        Cell::from(s.dep_time.to_string()),
+                        Cell::from("skipped").style(Style::default().fg(Color::Red)),
+                    ]).style(Style::default().add_modifier(Modifier::CROSSED_OUT))
+                } else {
+                    Row::new(vec![
+                        Cell::from(s.stop_name.to_string()),
+                        Cell::from(s.arr_time.to_string()),
+                        Cell::from(s.dep_time.to_string()),
+                        delay_cell(s.arr_delay.or(s.dep_delay)),
+                    ])
+                }
+            })
             .collect();
 
         let table = Table::new(rows)
             .style(Style::default().fg(Color::White))
             .header(
-                Row::new(vec!["Station", "Arr.", "Dep."])
+                Row::new(vec!["Station", "Arr.", "Dep.", "Delay"])
                     .style(Style::default().add_modifier(Modifier::BOLD))
             )
             .highlight_style(Style::default().fg(Color::Magenta))
             .highlight_symbol(">>")
-            .block(create_block(hovered, selected))
+            .block(create_block(hovered, selected).title(self.operating_days.to_string()))
             .widths(&[
-                Constraint::Percentage(70),
-                Constraint::Percentage(15),
-                Constraint::Percentage(15),
+                Constraint::Percentage(52),
+                Constraint::Percentage(14),
+                Constraint::Percentage(14),
+                Constraint::Percentage(20),
             ]);
 
         Ok(table)